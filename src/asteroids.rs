@@ -1,111 +1,332 @@
+use std::f32::consts::TAU;
+
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use rand::Rng;
 
 use crate::{
-    RES_HEIGHT, RES_WIDTH,
     collisions::{GROUP_ASTEROID, GROUP_PLAYER, GROUP_PROJECTILE},
-    // get_high_res_size,
+    despawn_all,
+    interpolation::Interpolated,
+    kinds::{
+        AsteroidKind, AsteroidKinds, AsteroidSprite, KindsFile, KindsLoader, apply_kinds_file,
+        load_kinds_file,
+    },
+    rng::GameRng,
+    rocks::rasterize,
+    ui::{GameState, RestartRun, init_game_state},
+    wrap::ScreenWrap,
 };
 
-#[derive(Component)]
-pub struct SpawnTimer {
-    pub timer: Timer,
+/// Loading the asteroid kinds, cleaning up asteroids and keeping them in line with their kind.
+/// When they're spawned is up to the [`WavePlugin`](crate::WavePlugin).
+pub struct AsteroidsPlugin;
+
+impl Plugin for AsteroidsPlugin {
+    fn build(&self, app: &mut App) {
+        init_game_state(app);
+
+        app.init_resource::<AsteroidKinds>()
+            .init_resource::<KindsFile>()
+            .init_asset::<AsteroidKinds>()
+            .register_asset_loader(KindsLoader)
+            .add_systems(Startup, load_kinds_file)
+            .add_systems(RestartRun, (despawn_all::<Asteroid>, despawn_all::<Blast>))
+            .add_systems(
+                Update,
+                (
+                    animate_blasts.run_if(not(in_state(GameState::Paused))),
+                    flash_asteroids.run_if(not(in_state(GameState::Paused))),
+                    apply_kinds_file,
+                    apply_asteroid_kinds.run_if(
+                        resource_changed::<AsteroidKinds>.and(not(resource_added::<AsteroidKinds>)),
+                    ),
+                )
+                    .chain(),
+            );
+    }
 }
 
+/// How long the flash of an exploding asteroid lasts.
+const BLAST_S: f32 = 0.3;
+
+const BLAST_COLOR: Color = Color::srgb(1.0, 0.6, 0.2);
+
+/// How long a damaged asteroid lights up.
+const FLASH_S: f32 = 0.1;
+
+/// Overbright, so even white rocks light up.
+const FLASH_COLOR: Color = Color::linear_rgb(4.0, 4.0, 4.0);
+
 #[derive(Component)]
 pub struct Asteroid {
+    /// Name of its [`AsteroidKind`].
+    pub kind: String,
     pub score: u32,
     pub scale: f32,
 }
 
-pub fn init_timer(mut commands: Commands) {
-    commands.spawn(SpawnTimer {
-        timer: Timer::from_seconds(1.0, TimerMode::Repeating),
-    });
+/// Damage an asteroid can still take before it breaks.
+#[derive(Component, Debug)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
 }
 
-pub fn manage_asteroids(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut timer_query: Query<&mut SpawnTimer>,
-    time: Res<Time>,
-    transform: Query<&Transform, With<Asteroid>>,
-    entity: Query<Entity, With<Asteroid>>,
-    // window: Single<&Window>,
-) {
-    // despawn logic
-    for (trans, ent) in transform.iter().zip(entity.iter()) {
-        if trans.translation.x > (RES_WIDTH) as f32 {
-            commands.entity(ent).despawn();
-        }
-        if trans.translation.x < -(RES_WIDTH as f32) {
-            commands.entity(ent).despawn();
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+}
+
+/// Lights a damaged asteroid up for a moment.
+#[derive(Component)]
+pub struct Flash {
+    timer: Timer,
+}
+
+impl Default for Flash {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(FLASH_S, TimerMode::Once),
         }
-        if trans.translation.y > (RES_HEIGHT) as f32 {
-            commands.entity(ent).despawn();
+    }
+}
+
+/// A piece of a split asteroid, relative to the asteroid it came from.
+#[derive(Clone, Debug)]
+pub struct Fragment {
+    pub scale: f32,
+    pub offset: Vec2,
+    pub linvel: Vec2,
+}
+
+/// Breaks an asteroid into fragments of `fragment_kind` following the split rules of its `kind`,
+/// more of them for bigger asteroids and harder impacts.
+/// Returns none if even two fragments would be too small, or if the kind doesn't split.
+///
+/// Mass goes with the area and the mass of the kind, so the fragments' masses add up to the asteroid's.
+/// Together they keep its momentum, and each of them keeps its spin, moving along with it around the old center.
+pub fn split_asteroid(
+    rng: &mut GameRng,
+    kind: &AsteroidKind,
+    fragment_kind: &AsteroidKind,
+    scale: f32,
+    velocity: &Velocity,
+    impact_speed: f32,
+) -> Vec<Fragment> {
+    let Some(split) = &kind.split else {
+        return Vec::new();
+    };
+    let wanted = ((impact_speed / split.speed_per_fragment) as usize)
+        .saturating_add(2)
+        .min(split.max_fragments as usize);
+    // scale of a single fragment with all of the asteroid's mass
+    let whole = scale * (kind.mass / fragment_kind.mass).sqrt();
+
+    for count in (2..=wanted).rev() {
+        let shares: Vec<f32> = (0..count).map(|_| rng.random_range(0.7..1.3)).collect();
+        let total: f32 = shares.iter().sum();
+        let scales: Vec<f32> = shares
+            .iter()
+            .map(|share| whole * (share / total).sqrt())
+            .collect();
+        if scales.iter().any(|scale| *scale < split.min_fragment_scale) {
+            continue;
         }
-        if trans.translation.y < -(RES_HEIGHT as f32) {
-            commands.entity(ent).despawn();
+
+        // evenly around the inside of the asteroid, each touching its outline
+        let radius = kind.collider.radius() * scale;
+        let start = rng.random_range(0.0..TAU);
+        let mut offsets: Vec<Vec2> = scales
+            .iter()
+            .enumerate()
+            .map(|(i, fragment_scale)| {
+                Vec2::from_angle(start + TAU * i as f32 / count as f32)
+                    * (radius - fragment_kind.collider.radius() * fragment_scale).max(0.0)
+            })
+            .collect();
+
+        // keep the center of mass where it was, then neither spreading out nor spinning
+        // around it adds any momentum
+        let center = offsets
+            .iter()
+            .zip(&scales)
+            .map(|(offset, fragment_scale)| *offset * fragment_scale * fragment_scale)
+            .sum::<Vec2>()
+            / (whole * whole);
+        for offset in &mut offsets {
+            *offset -= center;
         }
+
+        return offsets
+            .into_iter()
+            .zip(scales)
+            .map(|(offset, scale)| Fragment {
+                scale,
+                offset,
+                linvel: velocity.linvel
+                    + offset.perp() * velocity.angvel
+                    + offset / radius * split.fragment_spread,
+            })
+            .collect();
     }
 
-    // get spawn timer
-    let mut spawn_timer = match timer_query.single_mut() {
-        Ok(tim) => tim,
-        Err(e) => {
-            warn!("failed to get asteroid spawn timer: {}.", e);
-            return;
+    Vec::new()
+}
+
+/// Spawns a single asteroid of the given `kind`, its size, mass and score follow from `scale`.
+/// Generated outlines and rasterized sprites come from `rng`, so every asteroid looks different.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_asteroid(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    images: &mut Assets<Image>,
+    rng: &mut GameRng,
+    kind: &AsteroidKind,
+    pos: Vec3,
+    linvel: Vec2,
+    angvel: f32,
+    scale: f32,
+) -> Entity {
+    let collider = kind.collider.collider(scale, rng);
+    let (sprite, sprite_scale) = match &kind.sprite {
+        AsteroidSprite::Image {
+            path,
+            scale: image_scale,
+        } => (asset_server.load(path), scale * image_scale),
+        // one texel per canvas pixel
+        AsteroidSprite::Rasterized => {
+            let shade = rng.random_range(0.45..0.65);
+            let image = rasterize(&collider, kind.collider.radius() * scale, shade);
+            (images.add(image), 1.0)
         }
     };
-    spawn_timer.timer.tick(time.delta());
 
-    let mut rng = rand::rng();
+    let transform = Transform::from_translation(pos).with_scale(Vec3::splat(sprite_scale));
+    commands
+        .spawn((
+            Sprite {
+                color: Color::srgb_from_array(kind.tint),
+                ..Sprite::from_image(sprite)
+            },
+            transform,
+            Interpolated::from(transform),
+            Velocity { linvel, angvel },
+            GravityScale(0.0),
+            Damping {
+                linear_damping: 0.0,
+                angular_damping: 0.0,
+            },
+            Sleeping::disabled(),
+            RigidBody::Dynamic,
+            (
+                collider,
+                // sized in canvas pixels, not relative to the sprite
+                ColliderScale::Absolute(Vect::ONE),
+                ColliderMassProperties::Mass(kind.mass * scale * scale),
+                ActiveEvents::COLLISION_EVENTS,
+                CollisionGroups::new(
+                    Group::from_bits_truncate(GROUP_ASTEROID),
+                    Group::from_bits_truncate(GROUP_PLAYER | GROUP_PROJECTILE | GROUP_ASTEROID),
+                ),
+            ),
+            Asteroid {
+                kind: kind.name.clone(),
+                score: kind.score.at(scale),
+                scale,
+            },
+            kind.material,
+            Health::new(kind.hit_points * scale),
+            ScreenWrap::default(),
+        ))
+        .id()
+}
 
-    if !spawn_timer.timer.just_finished() || rng.random_bool(1.0 / 4.0) {
-        return;
+/// Picks up changed asteroid kinds while the game is running.
+fn apply_asteroid_kinds(
+    mut asteroids: Query<(
+        &Asteroid,
+        &mut Collider,
+        &mut ColliderMassProperties,
+        Option<&mut Health>,
+    )>,
+    kinds: Res<AsteroidKinds>,
+    mut rng: ResMut<GameRng>,
+) {
+    for (asteroid, mut collider, mut mass, health) in asteroids.iter_mut() {
+        // asteroids of a kind that's gone keep what they had
+        let Some(kind) = kinds.get(&asteroid.kind) else {
+            continue;
+        };
+        *mass = ColliderMassProperties::Mass(kind.mass * asteroid.scale * asteroid.scale);
+        // damaged asteroids stay just as damaged
+        if let Some(mut health) = health {
+            let max = kind.hit_points * asteroid.scale;
+            health.current *= max / health.max;
+            health.max = max;
+        }
+        // rocks drawn from their collider keep it, a new one wouldn't match the sprite anymore
+        let drawn = matches!(kind.sprite, AsteroidSprite::Rasterized);
+        if !drawn && !kind.collider.is_generated() {
+            *collider = kind.collider.collider(asteroid.scale, &mut rng);
+        }
     }
+}
 
-    let pos_x = rng.random_range(-(RES_WIDTH as i32 / 2)..(RES_WIDTH as i32 / 2));
-    let pos_y = rng.random_range(-(RES_HEIGHT as i32 / 2)..(RES_HEIGHT as i32 / 2));
-    let scale = rng.random_range(0.3..0.6);
-    let linvel_x = rng.random_range(-15..15);
-    let linvel_y = rng.random_range(-15..15);
-    let angvel = rng.random_range(-2..2);
-
-    let score: u32 = {
-        let m = (2.0_f32 - 4.0_f32) / (0.6_f32 - 0.3_f32);
-        let b = 4.0_f32 - m * 0.3_f32;
-        (m * scale + b).floor() as u32
-    };
+/// Flash of an explosive asteroid going off, covering everything it reaches.
+#[derive(Component)]
+pub struct Blast {
+    timer: Timer,
+}
 
-    // TODO: don't spawn near the player, limit spawning
-    // spawningggg
+pub fn spawn_blast(commands: &mut Commands, images: &mut Assets<Image>, pos: Vec3, radius: f32) {
+    let image = rasterize(&Collider::ball(radius), radius, 1.0);
     commands.spawn((
-        Sprite::from_image(asset_server.load("asteroids/1.png")),
-        Transform::from_xyz(pos_x as f32, pos_y as f32, 0.0).with_scale(Vec3::splat(scale / 40.0)),
-        Velocity {
-            linvel: Vec2 {
-                x: linvel_x as f32,
-                y: linvel_y as f32,
-            },
-            angvel: angvel as f32,
+        Sprite {
+            color: BLAST_COLOR,
+            ..Sprite::from_image(images.add(image))
         },
-        GravityScale(0.0),
-        Damping {
-            linear_damping: 0.0,
-            angular_damping: 0.0,
+        Transform::from_translation(pos),
+        Blast {
+            timer: Timer::from_seconds(BLAST_S, TimerMode::Once),
         },
-        Sleeping::disabled(),
-        RigidBody::Dynamic,
-        // Collider::ball(500.0 * scale * get_high_res_size(&window)),
-        Collider::ball(500.0 * scale * 3.0),
-        ActiveEvents::COLLISION_EVENTS,
-        Ccd::enabled(),
-        Asteroid { scale, score },
-        CollisionGroups::new(
-            Group::from_bits_truncate(GROUP_ASTEROID),
-            Group::from_bits_truncate(GROUP_PLAYER | GROUP_PROJECTILE | GROUP_ASTEROID),
-        ),
     ));
 }
+
+/// Fades blasts out until they're gone.
+fn animate_blasts(
+    mut commands: Commands,
+    mut blasts: Query<(Entity, &mut Blast, &mut Sprite)>,
+    time: Res<Time>,
+) {
+    for (entity, mut blast, mut sprite) in blasts.iter_mut() {
+        if blast.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        sprite
+            .color
+            .set_alpha(0.8 * blast.timer.fraction_remaining());
+    }
+}
+
+/// Shows damaged asteroids in a bright flash, then goes back to the tint of their kind.
+fn flash_asteroids(
+    mut commands: Commands,
+    mut asteroids: Query<(Entity, &Asteroid, &mut Flash, &mut Sprite)>,
+    kinds: Res<AsteroidKinds>,
+    time: Res<Time>,
+) {
+    for (entity, asteroid, mut flash, mut sprite) in asteroids.iter_mut() {
+        if !flash.timer.tick(time.delta()).finished() {
+            sprite.color = FLASH_COLOR;
+            continue;
+        }
+
+        let tint = kinds.get(&asteroid.kind).map_or([1.0; 3], |kind| kind.tint);
+        sprite.color = Color::srgb_from_array(tint);
+        commands.entity(entity).remove::<Flash>();
+    }
+}
@@ -1,4 +1,5 @@
 use bevy::{
+    ecs::event::EventReader,
     prelude::*,
     render::{
         camera::RenderTarget,
@@ -6,10 +7,21 @@ use bevy::{
             Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
         },
     },
+    window::WindowResized,
 };
 
 use crate::{Canvas, HIGH_RES_LAYERS, RES_HEIGHT, RES_WIDTH};
 
+/// Pixel-perfect cameras, the canvas they render to and the background.
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, (setup_background, setup_camera))
+            .add_systems(Update, fit_canvas);
+    }
+}
+
 /// Camera that renders the pixel-perfect world to the [`Canvas`].
 #[derive(Component)]
 pub struct InGameCamera;
@@ -68,3 +80,30 @@ pub fn setup_camera(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
     // here, the canvas and one of the sample sprites will be rendered by this camera
     commands.spawn((Camera2d, Msaa::Off, OuterCamera, HIGH_RES_LAYERS));
 }
+
+fn setup_background(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.spawn((
+        Sprite::from_image(asset_server.load("bg.png")),
+        Transform::from_xyz(0.0, 0.0, -10.0),
+    ));
+}
+
+/// Scales camera projection to fit the window (integer multiples only).
+fn fit_canvas(
+    mut resize_messages: EventReader<WindowResized>,
+    mut projection: Single<&mut Projection, With<OuterCamera>>,
+) {
+    let Projection::Orthographic(projection) = &mut **projection else {
+        return;
+    };
+    for window_resized in resize_messages.read() {
+        projection.scale = 1. / get_high_res_size(window_resized.width, window_resized.height);
+    }
+}
+
+/// Largest integer factor the canvas can be scaled by to fit the window.
+fn get_high_res_size(width: f32, height: f32) -> f32 {
+    let h_scale = width / RES_WIDTH as f32;
+    let v_scale = height / RES_HEIGHT as f32;
+    h_scale.min(v_scale).round()
+}
@@ -1,14 +1,54 @@
+use std::collections::HashSet;
+
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
-use rand::Rng;
 
 use crate::{
-    asteroids::Asteroid, get_high_res_size, player::Player, shooting::Projectile, ui::GameState,
+    FIXED_HZ,
+    asteroids::{Asteroid, Flash, Health, spawn_asteroid, spawn_blast, split_asteroid},
+    kinds::{AsteroidKinds, AsteroidMaterial},
+    mode::GameMode,
+    pickups::{Pickup, spawn_pickup},
+    player::{Invulnerable, Player},
+    rng::GameRng,
+    shooting::Projectile,
+    tuning::{PlayerTuning, StatsTuning},
+    ui::{GameState, RestartRun, init_game_state},
+    wrap::{wrapped_distance, wrapped_offset},
 };
 
 pub const GROUP_PLAYER: u32 = 0b0001;
 pub const GROUP_PROJECTILE: u32 = 0b0010;
 pub const GROUP_ASTEROID: u32 = 0b0100;
+pub const GROUP_PICKUP: u32 = 0b1000;
+
+/// Hits taking less health than this only get a gentler push and don't make the ship
+/// invulnerable, so a graze can't shield it from the crash right after.
+const INVULNERABLE_DAMAGE: f32 = 0.5;
+
+/// Game rules for everything that bumps into each other.
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        init_game_state(app);
+
+        app.init_resource::<GameRng>()
+            .init_resource::<AsteroidKinds>()
+            .init_resource::<PlayerTuning>()
+            .init_resource::<StatsTuning>()
+            .init_resource::<Stats>()
+            .init_resource::<GameMode>()
+            .add_systems(
+                FixedUpdate,
+                (collision_system, crash_system)
+                    .chain()
+                    .before(PhysicsSet::SyncBackend)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(RestartRun, reset_stats);
+    }
+}
 
 #[derive(Resource)]
 pub struct Stats {
@@ -16,19 +56,50 @@ pub struct Stats {
     pub health: f32,
 }
 
+impl FromWorld for Stats {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            score: 0,
+            health: world.resource::<StatsTuning>().starting_health,
+        }
+    }
+}
+
+fn reset_stats(mut stats: ResMut<Stats>, tuning: Res<StatsTuning>) {
+    stats.score = 0;
+    stats.health = tuning.starting_health;
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn collision_system(
     mut commands: Commands,
     mut collision_events: EventReader<CollisionEvent>,
-    q_player: Query<Entity, With<Player>>,
-    q_projectile: Query<Entity, With<Projectile>>,
-    q_asteroid: Query<Entity, With<Asteroid>>,
-    q2_asteroid: Query<(&Asteroid, &Transform, &Velocity)>,
-    q_vel: Query<&Velocity>,
+    q_player: Query<(Entity, &Transform, Has<Invulnerable>), With<Player>>,
+    q_projectile: Query<(&Projectile, &Velocity)>,
+    q_asteroid: Query<(
+        Entity,
+        &Asteroid,
+        &Transform,
+        &Velocity,
+        Option<&AsteroidMaterial>,
+    )>,
+    mut q_health: Query<&mut Health>,
+    q_pickup: Query<&Pickup>,
     mut stats: ResMut<Stats>,
     mut next_state: ResMut<NextState<GameState>>,
     asset_server: Res<AssetServer>,
-    window: Single<&Window>,
+    mut images: ResMut<Assets<Image>>,
+    kinds: Res<AsteroidKinds>,
+    player_tuning: Res<PlayerTuning>,
+    stats_tuning: Res<StatsTuning>,
+    mut rng: ResMut<GameRng>,
+    mode: Res<GameMode>,
 ) {
+    // several explosions at once are still a single hit
+    let mut player_hit = false;
+    // asteroids, projectiles and pickups that are used up, each one only counts once
+    let mut spent = HashSet::new();
+
     for event in collision_events.read() {
         if let CollisionEvent::Started(entity1, entity2, _) = event {
             let is_player1 = q_player.get(*entity1).is_ok();
@@ -40,135 +111,236 @@ pub fn collision_system(
             let is_asteroid1 = q_asteroid.get(*entity1).is_ok();
             let is_asteroid2 = q_asteroid.get(*entity2).is_ok();
 
+            let is_pickup1 = q_pickup.get(*entity1).is_ok();
+            let is_pickup2 = q_pickup.get(*entity2).is_ok();
+
             if (is_projectile1 && is_asteroid2) || (is_projectile2 && is_asteroid1) {
-                // animation
-                let mut ast_size = 0.0;
-                let mut location: Vec3 = Vec3::new(0.0, 0.0, 0.0);
-                let mut _velocity: Vec2 = Vec2::new(0.0, 0.0);
-                if let Ok((ast, trans, vel)) = q2_asteroid.get(*entity1) {
-                    stats.score += ast.score;
-                    ast_size = ast.scale;
-                    location = trans.translation;
-                    _velocity = vel.linvel;
-                } else if let Ok((ast, trans, vel)) = q2_asteroid.get(*entity2) {
-                    stats.score += ast.score;
-                    ast_size = ast.scale;
-                    location = trans.translation;
-                    _velocity = vel.linvel;
-                }
-                if is_asteroid1 {
-                    commands.entity(*entity1).despawn();
-                } else if is_asteroid2 {
-                    commands.entity(*entity2).despawn();
-                }
-                if ast_size == 0.0 {
+                let (projectile, asteroid) = if is_projectile1 {
+                    (*entity1, *entity2)
+                } else {
+                    (*entity2, *entity1)
+                };
+                // an asteroid that's already broken lets the projectile fly on
+                if spent.contains(&asteroid) || !spent.insert(projectile) {
                     continue;
                 }
-                {
-                    let mut rng = rand::rng();
-                    let ratio: f32 = 0.3 + rng.random::<f32>() * 0.4;
+                commands.entity(projectile).despawn();
+                info!("Projectile hit asteroid!");
 
-                    let ast_size1 = ast_size * ratio;
-                    let ast_size2 = ast_size * (1.0 - ratio);
+                let (Ok((_, _, _, vel, _)), Ok((shot, projectile_vel))) =
+                    (q_asteroid.get(asteroid), q_projectile.get(projectile))
+                else {
+                    continue;
+                };
+                let impact_speed = (projectile_vel.linvel - vel.linvel).length();
 
-                    if ast_size1 < 0.15 || ast_size2 < 0.15 {
+                // explosions can break more asteroids, which can explode in turn
+                let mut hit = vec![(asteroid, impact_speed, shot.damage)];
+                while let Some((asteroid, mut impact_speed, damage)) = hit.pop() {
+                    if spent.contains(&asteroid) {
                         continue;
                     }
+                    let Ok((_, ast, trans, vel, material)) = q_asteroid.get(asteroid) else {
+                        continue;
+                    };
+                    let material = material.copied().unwrap_or_default();
 
-                    let projectile_vel = if let Ok(proj_vel) = q_vel.get(*entity1) {
-                        proj_vel.linvel * 0.3
-                    } else if let Ok(proj_vel) = q_vel.get(*entity2) {
-                        proj_vel.linvel * 0.3
-                    } else {
-                        Vec2::ZERO
+                    let Ok(mut health) = q_health.get_mut(asteroid) else {
+                        continue;
                     };
+                    let armor = match material {
+                        AsteroidMaterial::Metal { armor } => armor,
+                        _ => 0.0,
+                    };
+                    health.current -= damage * (1.0 - armor);
+                    if health.current > 0.0 {
+                        commands.entity(asteroid).insert(Flash::default());
+                        continue;
+                    }
 
-                    let mut rng = rand::rng();
-                    let angle1 = rng.random_range(0.0..std::f32::consts::TAU);
-                    let angle2 = rng.random_range(0.0..std::f32::consts::TAU);
-                    
-                    let separation = 50.0;
-                    let perp_vel1 = Vec2::new(angle1.cos(), angle1.sin()) * separation * 0.3;
-                    let perp_vel2 = Vec2::new(angle2.cos(), angle2.sin()) * separation * 0.3;
-
-                    spawn_asteroid(
-                        &mut commands,
-                        &asset_server,
-                        location,
-                        projectile_vel + perp_vel1,
-                        0.0,
-                        ast_size1,
-                        &window,
-                    );
-                    spawn_asteroid(
-                        &mut commands,
-                        &asset_server,
-                        location,
-                        projectile_vel + perp_vel2,
-                        0.0,
-                        ast_size2,
-                        &window,
-                    );
-                }
-                if is_projectile1 && q_projectile.get(*entity1).is_ok() {
-                    commands.entity(*entity1).despawn();
-                } else if is_projectile2 && q_projectile.get(*entity2).is_ok() {
-                    commands.entity(*entity2).despawn();
+                    spent.insert(asteroid);
+                    commands.entity(asteroid).despawn();
+                    stats.score += ast.score;
+
+                    match material {
+                        AsteroidMaterial::Rock | AsteroidMaterial::Metal { .. } => {}
+                        // shatters as much as it can, however soft the hit
+                        AsteroidMaterial::Ice => impact_speed = f32::INFINITY,
+                        AsteroidMaterial::Explosive { radius, damage } => {
+                            info!("Asteroid exploded!");
+                            spawn_blast(&mut commands, &mut images, trans.translation, radius);
+
+                            let center = trans.translation.xy();
+                            for (other, other_ast, other_trans, _, _) in q_asteroid.iter() {
+                                let size = kinds
+                                    .get(&other_ast.kind)
+                                    .map_or(0.0, |kind| kind.collider.radius() * other_ast.scale);
+                                if !spent.contains(&other)
+                                    && wrapped_distance(other_trans.translation.xy(), center)
+                                        <= radius + size
+                                {
+                                    hit.push((other, 0.0, damage));
+                                }
+                            }
+
+                            if let Ok((player, player_transform, invulnerable)) = q_player.single()
+                            {
+                                let position = player_transform.translation.xy();
+                                if wrapped_distance(position, center)
+                                    <= radius + player_tuning.collider_radius
+                                    && *mode != GameMode::Practice
+                                    && !invulnerable
+                                    && !player_hit
+                                {
+                                    player_hit = true;
+                                    hurt_player(
+                                        &mut commands,
+                                        &mut stats,
+                                        &mut next_state,
+                                        &player_tuning,
+                                        player,
+                                        position,
+                                        center,
+                                        damage,
+                                    );
+                                }
+                            }
+                        }
+                        AsteroidMaterial::Crystal { heal } => {
+                            spawn_pickup(
+                                &mut commands,
+                                &mut images,
+                                trans.translation,
+                                vel.linvel,
+                                heal,
+                            );
+                        }
+                    }
+
+                    let Some(kind) = kinds.get(&ast.kind) else {
+                        continue;
+                    };
+                    let fragment_kind = kinds.fragments_of(kind);
+                    for fragment in
+                        split_asteroid(&mut rng, kind, fragment_kind, ast.scale, vel, impact_speed)
+                    {
+                        spawn_asteroid(
+                            &mut commands,
+                            &asset_server,
+                            &mut images,
+                            &mut rng,
+                            fragment_kind,
+                            trans.translation + fragment.offset.extend(0.0),
+                            fragment.linvel,
+                            vel.angvel,
+                            fragment.scale,
+                        );
+                    }
                 }
-                info!("Projectile hit asteroid!");
             }
 
-            if (is_player1 && is_asteroid2) || (is_player2 && is_asteroid1) {
-                stats.health -= 1.0;
-                info!("Player hit asteroid!");
-                if stats.health <= 0.0 {
-                    next_state.set(GameState::GameOver);
+            if (is_player1 && is_pickup2) || (is_player2 && is_pickup1) {
+                let pickup = if is_pickup1 { *entity1 } else { *entity2 };
+                let Ok(Pickup { heal, .. }) = q_pickup.get(pickup) else {
+                    continue;
+                };
+                if !spent.insert(pickup) {
+                    continue;
                 }
+                info!("Player collected a pickup!");
+                commands.entity(pickup).despawn();
+                stats.health = (stats.health + heal).min(stats_tuning.starting_health);
             }
         }
     }
 }
 
-fn spawn_asteroid(
-    commands: &mut Commands,
-    asset_server: &Res<AssetServer>,
-    pos: Vec3,
-    linvel: Vec2,
-    angvel: f32,
-    scale: f32,
-    window: &Single<&Window>,
+/// Hurts the ship by how hard it crashed into an asteroid, going by the contact forces
+/// of the last physics step. Grazing a pebble barely hurts, ramming a big rock is deadly.
+#[allow(clippy::too_many_arguments)]
+fn crash_system(
+    mut commands: Commands,
+    mut contact_force_events: EventReader<ContactForceEvent>,
+    q_player: Query<(&Transform, Has<Invulnerable>), With<Player>>,
+    q_asteroid: Query<&Transform, With<Asteroid>>,
+    mut stats: ResMut<Stats>,
+    mut next_state: ResMut<NextState<GameState>>,
+    tuning: Res<PlayerTuning>,
+    mode: Res<GameMode>,
 ) {
-    let score: u32 = {
-        let m = (2.0_f32 - 4.0_f32) / (0.6_f32 - 0.3_f32);
-        let b = 4.0_f32 - m * 0.3_f32;
-        (m * scale + b).floor() as u32
+    // touching several asteroids at once is still a single hit, by the hardest one
+    let crash = contact_force_events
+        .read()
+        .filter_map(|event| {
+            let (player, asteroid) = if q_player.contains(event.collider1) {
+                (event.collider1, event.collider2)
+            } else {
+                (event.collider2, event.collider1)
+            };
+            let (player_transform, invulnerable) = q_player.get(player).ok()?;
+            let asteroid_transform = q_asteroid.get(asteroid).ok()?;
+            (!invulnerable).then_some((
+                player,
+                player_transform.translation.xy(),
+                asteroid_transform.translation.xy(),
+                event.total_force_magnitude,
+            ))
+        })
+        .max_by(|a, b| a.3.total_cmp(&b.3));
+
+    let Some((player, position, source, force)) = crash else {
+        return;
     };
+    info!("Player hit asteroid!");
+    if *mode == GameMode::Practice {
+        return;
+    }
 
-    commands.spawn((
-        Sprite::from_image(asset_server.load("asteroids/1.png")),
-        Transform::from_xyz(pos.x, pos.y, pos.z).with_scale(Vec3::splat(scale / 40.0)),
-        Velocity {
-            linvel: Vec2 {
-                x: linvel.x,
-                y: linvel.y,
-            },
-            angvel: angvel,
-        },
-        GravityScale(0.0),
-        Damping {
-            linear_damping: 0.0,
-            angular_damping: 0.0,
-        },
-        Sleeping::disabled(),
-        RigidBody::Dynamic,
-        Collider::ball(500.0 * scale * get_high_res_size(window)),
-        // Collider::ball(0.5 * scale),
-        ActiveEvents::COLLISION_EVENTS,
-        Ccd::enabled(),
-        Asteroid { scale, score },
-        CollisionGroups::new(
-            Group::from_bits_truncate(GROUP_ASTEROID),
-            Group::from_bits_truncate(GROUP_PLAYER | GROUP_PROJECTILE | GROUP_ASTEROID),
-        ),
-    ));
+    // the force was applied over a single physics step
+    let impulse = force / FIXED_HZ as f32;
+    hurt_player(
+        &mut commands,
+        &mut stats,
+        &mut next_state,
+        &tuning,
+        player,
+        position,
+        source,
+        impulse * tuning.impact_damage,
+    );
+}
+
+/// Takes `damage` off the ship's health and pushes it away from `source`, the harder
+/// the more it hurt, so it doesn't keep tumbling into the same danger.
+/// After a proper hit it can't be hurt again for a while.
+#[allow(clippy::too_many_arguments)]
+fn hurt_player(
+    commands: &mut Commands,
+    stats: &mut Stats,
+    next_state: &mut NextState<GameState>,
+    tuning: &PlayerTuning,
+    player: Entity,
+    position: Vec2,
+    source: Vec2,
+    damage: f32,
+) {
+    let away = wrapped_offset(position, source).normalize_or_zero();
+    let knockback_speed = tuning.knockback_speed * (damage / INVULNERABLE_DAMAGE).min(1.0);
+    commands
+        .entity(player)
+        .entry::<Velocity>()
+        .and_modify(move |mut velocity| {
+            let towards = velocity.linvel.dot(away).min(0.0);
+            velocity.linvel += away * (knockback_speed - towards);
+        });
+    if damage >= INVULNERABLE_DAMAGE {
+        commands
+            .entity(player)
+            .insert(Invulnerable::new(tuning.invulnerability_s));
+    }
+
+    stats.health -= damage;
+    if stats.health <= 0.0 {
+        next_state.set(GameState::GameOver);
+    }
 }
@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{PluginGroupBuilder, ScheduleRunnerPlugin},
+    input::InputPlugin,
+    log::LogPlugin,
+    prelude::*,
+    state::app::StatesPlugin,
+};
+
+/// Replacement for `DefaultPlugins` that runs the gameplay loop without a window or GPU.
+///
+/// Assets are still registered so gameplay systems can keep loading sprites, they just never get rendered.
+pub struct HeadlessPlugins;
+
+impl PluginGroup for HeadlessPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add_group(
+                MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+                    1.0 / 60.0,
+                ))),
+            )
+            .add(LogPlugin::default())
+            .add(TransformPlugin)
+            .add(AssetPlugin::default())
+            .add(ImagePlugin::default_nearest())
+            .add(StatesPlugin)
+            .add(InputPlugin)
+    }
+}
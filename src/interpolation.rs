@@ -0,0 +1,74 @@
+use bevy::{
+    app::{RunFixedMainLoop, RunFixedMainLoopSystem},
+    prelude::*,
+};
+
+use crate::RES_HEIGHT;
+
+/// Smooths the movement of [`Interpolated`] entities between fixed timesteps.
+///
+/// Gameplay and physics only see the transforms of the last fixed step. Once all steps of a
+/// frame ran, the rendered transform is blended between the last two steps, and restored before
+/// the next steps run.
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            RunFixedMainLoop,
+            (
+                restore_physics_transforms.in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+                interpolate_transforms.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
+            ),
+        )
+        .add_systems(FixedLast, store_physics_transforms);
+    }
+}
+
+/// Position and rotation of the last two fixed steps.
+#[derive(Component)]
+pub struct Interpolated {
+    previous: (Vec3, Quat),
+    current: (Vec3, Quat),
+}
+
+impl From<Transform> for Interpolated {
+    fn from(transform: Transform) -> Self {
+        let step = (transform.translation, transform.rotation);
+        Self {
+            previous: step,
+            current: step,
+        }
+    }
+}
+
+fn restore_physics_transforms(mut query: Query<(&mut Transform, &Interpolated)>) {
+    for (mut transform, interpolated) in query.iter_mut() {
+        (transform.translation, transform.rotation) = interpolated.current;
+    }
+}
+
+fn store_physics_transforms(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in query.iter_mut() {
+        interpolated.previous = interpolated.current;
+        interpolated.current = (transform.translation, transform.rotation);
+
+        // wrapped around the screen, don't sweep across it
+        if interpolated.previous.0.distance(interpolated.current.0) > (RES_HEIGHT / 2) as f32 {
+            interpolated.previous = interpolated.current;
+        }
+    }
+}
+
+fn interpolate_transforms(
+    mut query: Query<(&mut Transform, &Interpolated)>,
+    time: Res<Time<Fixed>>,
+) {
+    let t = time.overstep_fraction();
+    for (mut transform, interpolated) in query.iter_mut() {
+        let (previous_translation, previous_rotation) = interpolated.previous;
+        let (current_translation, current_rotation) = interpolated.current;
+        transform.translation = previous_translation.lerp(current_translation, t);
+        transform.rotation = previous_rotation.slerp(current_rotation, t);
+    }
+}
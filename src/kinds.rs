@@ -0,0 +1,578 @@
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{
+    asset::{
+        AssetLoader, AssetPath, LoadContext,
+        io::{AssetSource, Reader},
+    },
+    prelude::*,
+};
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    rng::GameRng,
+    rocks::{lumpy_outline, outline_collider},
+};
+
+/// Asset the asteroid kinds are defined in, built into the game.
+/// A [`KindsFilePlugin`] replaces it with a file that can be changed while the game is running.
+pub const KINDS_PATH: &str = "asteroids.kinds.ron";
+
+const KINDS_SOURCE: &str = "kinds";
+
+/// Every kind of asteroid in the game, as defined in [`KINDS_PATH`].
+///
+/// Empty until the file is loaded, nothing is spawned before that.
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct AsteroidKinds {
+    pub kinds: Vec<AsteroidKind>,
+}
+
+/// What an asteroid looks like, how it flies and what happens when it's hit.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AsteroidKind {
+    /// Unique, asteroids and split rules refer to their kind by it.
+    pub name: String,
+    pub sprite: AsteroidSprite,
+    /// Color the sprite is multiplied with, in sRGB.
+    #[serde(default = "white")]
+    pub tint: [f32; 3],
+    #[serde(default)]
+    pub material: AsteroidMaterial,
+    /// Outline of an asteroid with scale 1, in canvas pixels.
+    pub collider: AsteroidShape,
+    /// Mass of an asteroid with scale 1, it grows with the area.
+    pub mass: f32,
+    /// Damage an asteroid with scale 1 takes before it breaks, bigger ones take more.
+    pub hit_points: f32,
+    pub score: ScoreCurve,
+    /// How asteroids of this kind break apart, without them they're just destroyed.
+    #[serde(default)]
+    pub split: Option<SplitRules>,
+    /// How often waves bring this kind compared to the others.
+    /// Kinds with a weight of 0 only show up as fragments.
+    pub spawn_weight: f32,
+}
+
+fn white() -> [f32; 3] {
+    [1.0; 3]
+}
+
+/// What an asteroid is made of, decides what happens when it's shot.
+#[derive(Component, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum AsteroidMaterial {
+    #[default]
+    Rock,
+    /// Shrugs off the `armor` share of all damage it takes.
+    Metal { armor: f32 },
+    /// Shatters into as many pieces as its split rules allow, however soft the hit.
+    Ice,
+    /// Hurts every asteroid and the ship within `radius` of it by `damage` when it breaks.
+    Explosive { radius: f32, damage: f32 },
+    /// Leaves a pickup behind that gives the ship `heal` health back.
+    Crystal { heal: f32 },
+}
+
+/// How asteroids of a kind are drawn.
+#[derive(Deserialize, Clone, Debug)]
+pub enum AsteroidSprite {
+    /// An image from the assets, drawn with `scale` for an asteroid of scale 1.
+    Image { path: String, scale: f32 },
+    /// Pixel art drawn from the collider of each asteroid.
+    Rasterized,
+}
+
+/// Collider outline. Points are relative to the center of the sprite, with y pointing up.
+///
+/// Only generated outlines are drawn as they are, for an image sprite it's up to the kind
+/// to give points that follow its silhouette. Nothing checks them against the image.
+#[derive(Deserialize, Clone, Debug)]
+pub enum AsteroidShape {
+    Ball {
+        radius: f32,
+    },
+    /// The convex hull of the points.
+    Polygon {
+        points: Vec<Vec2>,
+    },
+    /// Several convex hulls together, for rocks with dents in their outline.
+    Compound {
+        parts: Vec<Vec<Vec2>>,
+    },
+    /// A random outline for every asteroid, see [`lumpy_outline`].
+    Lumpy {
+        radius: f32,
+        lumpiness: f32,
+        corners: Range<u32>,
+    },
+}
+
+impl AsteroidShape {
+    /// Collider for a new asteroid, generated shapes draw their outline from `rng`.
+    pub fn collider(&self, scale: f32, rng: &mut GameRng) -> Collider {
+        match self {
+            AsteroidShape::Ball { radius } => Collider::ball(radius * scale),
+            AsteroidShape::Polygon { points } => hull(points, scale),
+            AsteroidShape::Compound { parts } => Collider::compound(
+                parts
+                    .iter()
+                    .map(|points| (Vect::ZERO, 0.0, hull(points, scale)))
+                    .collect(),
+            ),
+            AsteroidShape::Lumpy {
+                radius,
+                lumpiness,
+                corners,
+            } => outline_collider(&lumpy_outline(
+                rng,
+                radius * scale,
+                *lumpiness,
+                corners.clone(),
+            )),
+        }
+    }
+
+    /// Whether every asteroid gets an outline of its own.
+    pub fn is_generated(&self) -> bool {
+        matches!(self, AsteroidShape::Lumpy { .. })
+    }
+
+    /// Distance of the point furthest from the center at scale 1.
+    pub fn radius(&self) -> f32 {
+        match self {
+            AsteroidShape::Ball { radius } => *radius,
+            AsteroidShape::Polygon { points } => max_length(points),
+            AsteroidShape::Compound { parts } => parts
+                .iter()
+                .map(|points| max_length(points))
+                .fold(0.0, f32::max),
+            AsteroidShape::Lumpy { radius, .. } => *radius,
+        }
+    }
+
+    /// Whether every polygon spans an area, otherwise there's no hull to collide with.
+    fn is_valid(&self) -> bool {
+        match self {
+            AsteroidShape::Ball { radius } => *radius > 0.0 && radius.is_finite(),
+            AsteroidShape::Polygon { points } => is_finite_hull(points),
+            AsteroidShape::Compound { parts } => {
+                !parts.is_empty() && parts.iter().all(|points| is_finite_hull(points))
+            }
+            // around the center whichever way the corners are spread, and never down to it
+            AsteroidShape::Lumpy {
+                radius,
+                lumpiness,
+                corners,
+            } => {
+                *radius > 0.0
+                    && radius.is_finite()
+                    && (0.0..1.0).contains(lumpiness)
+                    && corners.start >= 4
+                    && !corners.is_empty()
+            }
+        }
+    }
+}
+
+fn is_finite_hull(points: &[Vec2]) -> bool {
+    points.iter().all(|point| point.is_finite()) && Collider::convex_hull(points).is_some()
+}
+
+fn hull(points: &[Vec2], scale: f32) -> Collider {
+    let points: Vec<Vec2> = points.iter().map(|point| *point * scale).collect();
+    Collider::convex_hull(&points).expect("polygons are checked when the kinds are loaded")
+}
+
+fn max_length(points: &[Vec2]) -> f32 {
+    points
+        .iter()
+        .map(|point| point.length())
+        .fold(0.0, f32::max)
+}
+
+/// Score for destroying an asteroid, going linearly from `score.start` at `scale.start`
+/// to `score.end` at `scale.end` and on in the same way beyond.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ScoreCurve {
+    pub scale: Range<f32>,
+    pub score: Range<f32>,
+}
+
+impl ScoreCurve {
+    pub fn at(&self, scale: f32) -> u32 {
+        let t = (scale - self.scale.start) / (self.scale.end - self.scale.start);
+        let score = self.score.start + (self.score.end - self.score.start) * t;
+        score.floor().max(0.0) as u32
+    }
+}
+
+/// How an asteroid breaks into fragments when it's shot.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SplitRules {
+    /// Kind of the fragments, the asteroid's own kind if not given.
+    #[serde(default)]
+    pub into: Option<String>,
+    /// Most pieces an asteroid breaks into, at least 2.
+    pub max_fragments: u32,
+    /// Asteroids only split if every fragment ends up at least this big.
+    pub min_fragment_scale: f32,
+    /// Impact speed it takes for each fragment beyond the first two.
+    pub speed_per_fragment: f32,
+    /// Speed fragments drift apart with.
+    pub fragment_spread: f32,
+}
+
+#[derive(Debug)]
+pub enum KindsError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Invalid { field: String, reason: &'static str },
+}
+
+impl fmt::Display for KindsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KindsError::Io(e) => write!(f, "failed to read asteroid kinds: {e}"),
+            KindsError::Parse(e) => write!(f, "invalid asteroid kinds: {e}"),
+            KindsError::Invalid { field, reason } => {
+                write!(f, "invalid asteroid kind value `{field}`: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for KindsError {}
+
+impl AsteroidKinds {
+    pub fn load(path: &Path) -> Result<Self, KindsError> {
+        let content = fs::read(path).map_err(KindsError::Io)?;
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &[u8]) -> Result<Self, KindsError> {
+        let kinds: Self = ron::de::from_bytes(content).map_err(KindsError::Parse)?;
+        kinds.validate()?;
+        Ok(kinds)
+    }
+
+    /// Rejects kinds the game can't work with.
+    pub fn validate(&self) -> Result<(), KindsError> {
+        fn check(
+            ok: bool,
+            kind: &str,
+            field: &str,
+            reason: &'static str,
+        ) -> Result<(), KindsError> {
+            if ok {
+                Ok(())
+            } else {
+                Err(KindsError::Invalid {
+                    field: format!("{kind}.{field}"),
+                    reason,
+                })
+            }
+        }
+
+        if !self.kinds.iter().any(|kind| kind.spawn_weight > 0.0) {
+            return Err(KindsError::Invalid {
+                field: "spawn_weight".into(),
+                reason: "must be positive for at least one kind",
+            });
+        }
+
+        let mut names = HashSet::new();
+        for kind in &self.kinds {
+            let name = kind.name.as_str();
+            check(names.insert(name), name, "name", "must be unique")?;
+
+            // infinite sizes and weights overflow when sprites are drawn and kinds are picked
+            let mut values = vec![
+                (kind.mass, "mass"),
+                (kind.hit_points, "hit_points"),
+                (kind.score.scale.start, "score.scale"),
+                (kind.score.scale.end, "score.scale"),
+                (kind.score.score.start, "score.score"),
+                (kind.score.score.end, "score.score"),
+                (kind.spawn_weight, "spawn_weight"),
+            ];
+            if let AsteroidSprite::Image { scale, .. } = kind.sprite {
+                values.push((scale, "sprite.scale"));
+            }
+            match kind.material {
+                AsteroidMaterial::Rock | AsteroidMaterial::Ice => {}
+                AsteroidMaterial::Metal { armor } => values.push((armor, "material")),
+                AsteroidMaterial::Explosive { radius, damage } => {
+                    values.extend([(radius, "material"), (damage, "material")]);
+                }
+                AsteroidMaterial::Crystal { heal } => values.push((heal, "material")),
+            }
+            if let Some(split) = &kind.split {
+                values.extend([
+                    (split.min_fragment_scale, "split.min_fragment_scale"),
+                    (split.speed_per_fragment, "split.speed_per_fragment"),
+                    (split.fragment_spread, "split.fragment_spread"),
+                ]);
+            }
+            for (value, field) in values {
+                check(value.is_finite(), name, field, "must be a finite number")?;
+            }
+            check(
+                match &kind.sprite {
+                    AsteroidSprite::Image { scale, .. } => *scale > 0.0,
+                    AsteroidSprite::Rasterized => true,
+                },
+                name,
+                "sprite.scale",
+                "must be positive",
+            )?;
+            check(
+                kind.collider.is_valid(),
+                name,
+                "collider",
+                "must enclose a finite area around the center",
+            )?;
+            check(
+                kind.tint
+                    .iter()
+                    .all(|channel| (0.0..=1.0).contains(channel)),
+                name,
+                "tint",
+                "must be between 0 and 1",
+            )?;
+            check(
+                match kind.material {
+                    AsteroidMaterial::Rock | AsteroidMaterial::Ice => true,
+                    AsteroidMaterial::Metal { armor } => (0.0..1.0).contains(&armor),
+                    AsteroidMaterial::Explosive { radius, damage } => radius > 0.0 && damage >= 0.0,
+                    AsteroidMaterial::Crystal { heal } => heal > 0.0,
+                },
+                name,
+                "material",
+                "must have an armor below 1, a positive radius and heal, and no negative damage",
+            )?;
+            check(kind.mass > 0.0, name, "mass", "must be positive")?;
+            check(
+                kind.hit_points > 0.0,
+                name,
+                "hit_points",
+                "must be positive",
+            )?;
+            check(
+                kind.score.scale.start != kind.score.scale.end,
+                name,
+                "score.scale",
+                "must not be empty",
+            )?;
+            check(
+                kind.spawn_weight >= 0.0,
+                name,
+                "spawn_weight",
+                "must not be negative",
+            )?;
+
+            let Some(split) = &kind.split else {
+                continue;
+            };
+            check(
+                split
+                    .into
+                    .as_ref()
+                    .is_none_or(|into| self.kinds.iter().any(|kind| kind.name == *into)),
+                name,
+                "split.into",
+                "must be the name of a kind",
+            )?;
+            check(
+                split.max_fragments >= 2,
+                name,
+                "split.max_fragments",
+                "must be at least 2",
+            )?;
+            check(
+                split.min_fragment_scale > 0.0,
+                name,
+                "split.min_fragment_scale",
+                "must be positive",
+            )?;
+            check(
+                split.speed_per_fragment > 0.0,
+                name,
+                "split.speed_per_fragment",
+                "must be positive",
+            )?;
+            check(
+                split.fragment_spread >= 0.0,
+                name,
+                "split.fragment_spread",
+                "must not be negative",
+            )?;
+        }
+
+        // rolled against when picking a kind
+        let total: f32 = self.kinds.iter().map(|kind| kind.spawn_weight).sum();
+        if !total.is_finite() {
+            return Err(KindsError::Invalid {
+                field: "spawn_weight".into(),
+                reason: "must add up to a finite number",
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AsteroidKind> {
+        self.kinds.iter().find(|kind| kind.name == name)
+    }
+
+    /// Kind of the fragments an asteroid of the given kind breaks into.
+    pub fn fragments_of<'a>(&'a self, kind: &'a AsteroidKind) -> &'a AsteroidKind {
+        kind.split
+            .as_ref()
+            .and_then(|split| split.into.as_deref())
+            .and_then(|into| self.get(into))
+            .unwrap_or(kind)
+    }
+
+    /// A random kind for the next asteroid of a wave, by spawn weight.
+    pub fn pick(&self, rng: &mut GameRng) -> Option<&AsteroidKind> {
+        let total: f32 = self.kinds.iter().map(|kind| kind.spawn_weight).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut roll = rng.random_range(0.0..total);
+        self.kinds
+            .iter()
+            .filter(|kind| kind.spawn_weight > 0.0)
+            .find(|kind| {
+                roll -= kind.spawn_weight;
+                roll < 0.0
+            })
+            // rounding can leave a tiny rest at the very end
+            .or_else(|| self.kinds.iter().rfind(|kind| kind.spawn_weight > 0.0))
+    }
+
+    /// Radius of the biggest kind at scale 1.
+    pub fn max_radius(&self) -> f32 {
+        self.kinds
+            .iter()
+            .map(|kind| kind.collider.radius())
+            .fold(0.0, f32::max)
+    }
+}
+
+/// Loads the asteroid kinds from the file at `path` instead of the ones built into the game,
+/// and applies changes to that file while the game is running.
+///
+/// Has to be added before the `AssetPlugin`, otherwise the file can't be watched.
+pub struct KindsFilePlugin {
+    pub path: PathBuf,
+}
+
+impl Plugin for KindsFilePlugin {
+    fn build(&self, app: &mut App) {
+        let path = std::path::absolute(&self.path).unwrap_or_else(|_| self.path.clone());
+        let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+            warn!("can't watch asteroid kinds file {}", path.display());
+            return;
+        };
+
+        let dir = dir.to_string_lossy().into_owned();
+        app.register_asset_source(
+            KINDS_SOURCE,
+            AssetSource::build()
+                .with_reader(AssetSource::get_default_reader(dir.clone()))
+                .with_watcher(AssetSource::get_default_watcher(
+                    dir,
+                    Duration::from_millis(300),
+                )),
+        )
+        .insert_resource(KindsFile {
+            path: AssetPath::from_path(Path::new(file_name))
+                .with_source(KINDS_SOURCE)
+                .into_owned(),
+            handle: Handle::default(),
+        });
+    }
+}
+
+/// The asteroid kinds asset, kept loaded so it gets reloaded on changes.
+#[derive(Resource)]
+pub(crate) struct KindsFile {
+    path: AssetPath<'static>,
+    handle: Handle<AsteroidKinds>,
+}
+
+impl Default for KindsFile {
+    fn default() -> Self {
+        Self {
+            path: AssetPath::from(KINDS_PATH),
+            handle: Handle::default(),
+        }
+    }
+}
+
+/// Loads and validates asteroid kinds through the asset server.
+pub(crate) struct KindsLoader;
+
+impl AssetLoader for KindsLoader {
+    type Asset = AsteroidKinds;
+    type Settings = ();
+    type Error = KindsError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<AsteroidKinds, KindsError> {
+        let mut content = Vec::new();
+        reader
+            .read_to_end(&mut content)
+            .await
+            .map_err(KindsError::Io)?;
+        AsteroidKinds::parse(&content)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["kinds.ron"]
+    }
+}
+
+pub(crate) fn load_kinds_file(mut kinds_file: ResMut<KindsFile>, asset_server: Res<AssetServer>) {
+    kinds_file.handle = asset_server.load(kinds_file.path.clone());
+}
+
+/// Replaces the [`AsteroidKinds`] resource once the file is loaded, and whenever it changed if it's watched.
+pub(crate) fn apply_kinds_file(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<AsteroidKinds>>,
+    assets: Res<Assets<AsteroidKinds>>,
+    kinds_file: Res<KindsFile>,
+) {
+    for event in events.read() {
+        if !event.is_loaded_with_dependencies(&kinds_file.handle)
+            && !event.is_modified(&kinds_file.handle)
+        {
+            continue;
+        }
+        let Some(kinds) = assets.get(&kinds_file.handle) else {
+            continue;
+        };
+
+        commands.insert_resource(kinds.clone());
+        info!("applied asteroid kinds from {KINDS_PATH}");
+    }
+}
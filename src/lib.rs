@@ -0,0 +1,110 @@
+use bevy::{prelude::*, render::view::RenderLayers};
+use bevy_rapier2d::prelude::*;
+
+pub mod asteroids;
+pub mod camera;
+pub mod collisions;
+pub mod headless;
+pub mod interpolation;
+pub mod kinds;
+pub mod menu;
+pub mod mode;
+pub mod pause;
+pub mod pickups;
+pub mod player;
+pub mod replay;
+pub mod rng;
+pub mod rocks;
+pub mod shooting;
+pub mod tuning;
+pub mod ui;
+pub mod waves;
+pub mod wrap;
+
+pub use asteroids::AsteroidsPlugin;
+pub use camera::CameraPlugin;
+pub use collisions::CollisionPlugin;
+pub use headless::HeadlessPlugins;
+pub use interpolation::InterpolationPlugin;
+pub use menu::MenuPlugin;
+pub use mode::GameModePlugin;
+pub use pause::PausePlugin;
+pub use pickups::PickupPlugin;
+pub use player::PlayerPlugin;
+pub use shooting::ShootingPlugin;
+pub use ui::UiPlugin;
+pub use waves::WavePlugin;
+pub use wrap::ScreenWrapPlugin;
+
+// The whole world is measured in canvas pixels: positions, speeds and collider sizes
+// don't depend on the size of the window the canvas is shown in.
+// Projectiles move several times their own size in a single step, so they use soft CCD.
+// Everything else is slow enough for its size to do without.
+
+/// In-game resolution width.
+pub const RES_WIDTH: u32 = 320;
+
+/// In-game resolution height.
+pub const RES_HEIGHT: u32 = 180;
+
+/// Render layers for high-resolution rendering.
+pub const HIGH_RES_LAYERS: RenderLayers = RenderLayers::layer(1);
+
+/// Rate gameplay and physics run at in `FixedUpdate`, independent of the frame rate.
+pub const FIXED_HZ: f64 = 60.0;
+
+/// Everything needed to play the game: physics plus all gameplay plugins.
+/// The [`CameraPlugin`] is left out when running `headless`.
+#[derive(Default)]
+pub struct SpaceGamePlugin {
+    pub headless: bool,
+    /// Start the first run right away instead of showing the main menu.
+    pub skip_menu: bool,
+}
+
+impl Plugin for SpaceGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(FIXED_HZ))
+            .insert_resource(TimestepMode::Fixed {
+                dt: 1.0 / FIXED_HZ as f32,
+                substeps: 1,
+            })
+            .add_plugins(
+                RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0).in_fixed_schedule(),
+            )
+            .add_plugins((
+                InterpolationPlugin,
+                ScreenWrapPlugin,
+                PlayerPlugin,
+                AsteroidsPlugin,
+                WavePlugin,
+                PickupPlugin,
+                ShootingPlugin,
+                CollisionPlugin,
+                UiPlugin,
+                GameModePlugin,
+                PausePlugin,
+                MenuPlugin,
+            ));
+
+        if self.skip_menu {
+            app.add_systems(Startup, ui::skip_main_menu);
+        }
+
+        if !self.headless {
+            app.add_plugins(CameraPlugin);
+        }
+    }
+}
+
+/// Despawns every entity with the marker component `C`, used to clean up when a run restarts.
+pub fn despawn_all<C: Component>(mut commands: Commands, query: Query<Entity, With<C>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Low-resolution texture that contains the pixel-perfect world.
+/// Canvas itself is rendered to the high-resolution world.
+#[derive(Component)]
+pub struct Canvas;
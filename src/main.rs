@@ -1,52 +1,147 @@
 // ...existing code...
 
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 
+use bevy::app::ScheduleRunnerPlugin;
 use bevy::dev_tools::fps_overlay::{FpsOverlayConfig, FpsOverlayPlugin};
-use bevy::ecs::event::EventReader;
-use bevy::window::WindowResized;
-use bevy::{prelude::*, render::view::RenderLayers};
+use bevy::prelude::*;
+use bevy::window::{MonitorSelection, WindowMode, WindowResolution};
 use bevy_embedded_assets::{EmbeddedAssetPlugin, PluginMode};
 use bevy_kira_audio::AudioPlugin;
 use bevy_rapier2d::prelude::*;
+use clap::{Parser, ValueEnum, builder::FalseyValueParser};
+use spacegame::{
+    HeadlessPlugins, SpaceGamePlugin,
+    kinds::KindsFilePlugin,
+    mode::GameMode,
+    replay::{RecordPlugin, Replay, ReplayPlugin},
+    rng::GameRng,
+    tuning::{Tuning, TuningPlugin},
+};
+
+/// A bad 2D game where you shoot at asteroids.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Seed of the first run, random if not given.
+    #[arg(long, env = "SPACEGAME_SEED")]
+    seed: Option<u64>,
+
+    /// Rules to play by, preselected in the main menu.
+    #[arg(long, value_enum, default_value_t = Mode::Classic)]
+    mode: Mode,
+
+    /// Window width in pixels.
+    #[arg(long, default_value_t = 1280)]
+    width: u32,
+
+    /// Window height in pixels.
+    #[arg(long, default_value_t = 720)]
+    height: u32,
+
+    /// Start in borderless fullscreen on the current monitor.
+    #[arg(long, conflicts_with_all = ["width", "height"])]
+    fullscreen: bool,
+
+    /// Run the gameplay loop without a window or GPU.
+    #[arg(long, env = "SPACEGAME_HEADLESS", value_parser = FalseyValueParser::new())]
+    headless: bool,
+
+    /// Record the input of the whole session to this file.
+    #[arg(long, env = "SPACEGAME_RECORD", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Play back a recorded session instead of reading the keyboard.
+    #[arg(long, env = "SPACEGAME_REPLAY")]
+    replay: Option<PathBuf>,
+
+    /// Balance the game with this tuning file, changes are applied while running.
+    #[arg(long, env = "SPACEGAME_TUNING")]
+    tuning: Option<PathBuf>,
+
+    /// Define the asteroid kinds with this file instead of the built-in one, changes are applied while running.
+    #[arg(long, env = "SPACEGAME_KINDS")]
+    kinds: Option<PathBuf>,
+
+    /// Hide the FPS overlay.
+    #[arg(long)]
+    no_fps: bool,
+
+    /// Draw the outlines of all colliders.
+    #[arg(long)]
+    debug_render: bool,
+}
 
-mod player;
-use player::*;
-
-mod camera;
-use camera::*;
-
-mod asteroids;
-use asteroids::*;
-
-mod collisions;
-use collisions::*;
-
-mod shooting;
-use shooting::*;
-
-mod ui;
-use ui::*;
-
-/// In-game resolution width.
-const RES_WIDTH: u32 = 320;
-
-/// In-game resolution height.
-const RES_HEIGHT: u32 = 180;
+#[derive(ValueEnum, Clone, Copy)]
+enum Mode {
+    Classic,
+    Practice,
+    TimeAttack,
+}
 
-/// Render layers for high-resolution rendering.
-const HIGH_RES_LAYERS: RenderLayers = RenderLayers::layer(1);
+impl From<Mode> for GameMode {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::Classic => GameMode::Classic,
+            Mode::Practice => GameMode::Practice,
+            Mode::TimeAttack => GameMode::TimeAttack,
+        }
+    }
+}
 
 fn main() {
-    App::new()
-        .add_plugins(EmbeddedAssetPlugin {
-            mode: PluginMode::ReplaceDefault,
-        })
-        .add_plugins(
+    let cli = Cli::parse();
+
+    let replay = cli.replay.as_ref().map(|path| {
+        Replay::load(path).unwrap_or_else(|e| fail(format!("{}: {e}", path.display())))
+    });
+
+    let tuning = cli.tuning.map(|path| {
+        let tuning =
+            Tuning::load(&path).unwrap_or_else(|e| fail(format!("{}: {e}", path.display())));
+        TuningPlugin {
+            tuning,
+            path: Some(path),
+        }
+    });
+
+    let mut app = App::new();
+    app.add_plugins(EmbeddedAssetPlugin {
+        mode: PluginMode::ReplaceDefault,
+    });
+
+    // have to come before the asset plugin to watch the tuning and kinds files
+    if let Some(tuning) = tuning {
+        app.add_plugins(tuning);
+    }
+    if let Some(path) = cli.kinds {
+        app.add_plugins(KindsFilePlugin { path });
+    }
+
+    if let Some(seed) = cli.seed {
+        app.insert_resource(GameRng::new(seed));
+    }
+    app.insert_resource(GameMode::from(cli.mode));
+
+    if cli.headless && replay.is_some() {
+        // nothing to wait for when the input comes from a file
+        app.add_plugins(HeadlessPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::ZERO)));
+    } else if cli.headless {
+        app.add_plugins(HeadlessPlugins);
+    } else {
+        let mode = if cli.fullscreen {
+            WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+        } else {
+            WindowMode::Windowed
+        };
+
+        app.add_plugins(
             DefaultPlugins
                 .set(WindowPlugin {
                     primary_window: Some(Window {
                         title: "Space Game".into(),
+                        resolution: WindowResolution::new(cli.width as f32, cli.height as f32),
+                        mode,
                         ..default()
                     }),
                     ..default()
@@ -54,87 +149,45 @@ fn main() {
                 .set(ImagePlugin::default_nearest()),
         )
         .add_plugins(AudioPlugin)
-        .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0))
-        .add_plugins(FpsOverlayPlugin {
-            config: FpsOverlayConfig {
-                text_config: TextFont {
-                    font_size: 20.0,
-                    ..default()
-                },
-                refresh_interval: Duration::from_millis(50),
-                ..default()
-            },
-        })
-        .insert_resource(ProjectilesData { last_shoot: 0.0 })
-        .insert_state(GameState::InGame)
-        .insert_resource(Stats {
-            score: 0,
-            health: 3.0,
-        })
         .add_plugins(RapierDebugRenderPlugin {
             default_collider_debug: ColliderDebug::AlwaysRender,
-            enabled: false,
+            enabled: cli.debug_render,
             mode: DebugRenderMode::all(),
             ..default()
-        })
-        .add_systems(
-            Startup,
-            (
-                setup_background,
-                setup_camera,
-                setup_player,
-                init_timer,
-                spawn_stats,
-            ),
-        )
-        .add_systems(
-            Update,
-            (
-                control_player.run_if(in_state(GameState::InGame)),
-                fit_canvas,
-                keep_player,
-                manage_projectiles,
-                manage_asteroids.run_if(in_state(GameState::InGame)),
-                shoot.run_if(in_state(GameState::InGame)),
-                collision_system.run_if(in_state(GameState::InGame)),
-                update_stats.run_if(in_state(GameState::InGame)),
-                handle_game_over_input.run_if(in_state(GameState::GameOver)),
-            ),
-        )
-        .add_systems(OnEnter(GameState::GameOver), spawn_game_over_ui)
-        .add_systems(OnExit(GameState::GameOver), despawn_game_over_ui)
-        .run();
-}
+        });
 
-/// Low-resolution texture that contains the pixel-perfect world.
-/// Canvas itself is rendered to the high-resolution world.
-#[derive(Component)]
-struct Canvas;
+        if !cli.no_fps {
+            app.add_plugins(FpsOverlayPlugin {
+                config: FpsOverlayConfig {
+                    text_config: TextFont {
+                        font_size: 20.0,
+                        ..default()
+                    },
+                    refresh_interval: Duration::from_millis(50),
+                    ..default()
+                },
+            });
+        }
+    }
 
-fn setup_background(asset_server: Res<AssetServer>, mut commands: Commands) {
-    commands.spawn((
-        Sprite::from_image(asset_server.load("bg.png")),
-        Transform::from_xyz(0.0, 0.0, -10.0),
-    ));
-}
+    // nobody could pick anything in the menu, but replays go through it like the recorded session
+    let skip_menu = cli.headless && replay.is_none();
 
-/// Scales camera projection to fit the window (integer multiples only).
-fn fit_canvas(
-    mut resize_messages: EventReader<WindowResized>,
-    mut projection: Single<&mut Projection, With<OuterCamera>>,
-) {
-    let Projection::Orthographic(projection) = &mut **projection else {
-        return;
-    };
-    for window_resized in resize_messages.read() {
-        let h_scale = window_resized.width / RES_WIDTH as f32;
-        let v_scale = window_resized.height / RES_HEIGHT as f32;
-        projection.scale = 1. / h_scale.min(v_scale).round();
+    if let Some(replay) = replay {
+        app.add_plugins(ReplayPlugin { replay });
+    } else if let Some(path) = cli.record {
+        app.add_plugins(RecordPlugin { path });
     }
+
+    app.add_plugins(SpaceGamePlugin {
+        headless: cli.headless,
+        skip_menu,
+    })
+    .run();
 }
 
-fn get_high_res_size(window: &Window) -> f32 {
-    let h_scale = window.width() / RES_WIDTH as f32;
-    let v_scale = window.height() / RES_HEIGHT as f32;
-    h_scale.min(v_scale).round()
+/// Reports a problem with the startup options and quits.
+fn fail(message: String) -> ! {
+    eprintln!("error: {message}");
+    std::process::exit(1);
 }
@@ -0,0 +1,329 @@
+use bevy::{
+    prelude::*,
+    ui::UiSystem,
+    window::{MonitorSelection, PrimaryWindow, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    despawn_all,
+    mode::GameMode,
+    ui::{GameState, HighScores, RestartRun, init_game_state},
+};
+
+const WHITE_COLOR: Color = Color::srgb(1.0, 1.0, 1.0);
+const SELECTED_COLOR: Color = Color::srgb(1.5, 1.2, 0.0);
+
+/// The main menu shown at launch and the pause menu, both driven by the keyboard or the mouse.
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        init_game_state(app);
+
+        app.init_resource::<Menu>()
+            .init_resource::<GameMode>()
+            .init_resource::<HighScores>()
+            .add_event::<MenuPointer>()
+            .add_systems(PreUpdate, point_at_menu.after(UiSystem::Focus))
+            .add_systems(
+                Update,
+                (navigate_menu, refresh_menu_items)
+                    .chain()
+                    .run_if(in_state(GameState::MainMenu).or(in_state(GameState::Paused))),
+            )
+            .add_systems(OnEnter(GameState::MainMenu), open_main_menu)
+            .add_systems(OnExit(GameState::MainMenu), despawn_all::<MenuNode>)
+            .add_systems(OnEnter(GameState::Paused), open_pause_menu)
+            .add_systems(OnExit(GameState::Paused), despawn_all::<MenuNode>);
+    }
+}
+
+/// Which page of the menu is open and which item is selected on it.
+#[derive(Resource, Default)]
+pub struct Menu {
+    /// Page the menu was opened on, going back leads here.
+    pub root: MenuPage,
+    pub page: MenuPage,
+    pub selected: usize,
+}
+
+impl Menu {
+    fn open(root: MenuPage) -> Self {
+        Self {
+            root,
+            page: root,
+            selected: 0,
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuPage {
+    #[default]
+    Title,
+    Pause,
+    Settings,
+    HighScores,
+}
+
+impl MenuPage {
+    fn title(self) -> &'static str {
+        match self {
+            MenuPage::Title => "Space Game",
+            MenuPage::Pause => "Paused",
+            MenuPage::Settings => "Settings",
+            MenuPage::HighScores => "High Scores",
+        }
+    }
+
+    fn items(self) -> &'static [MenuItem] {
+        match self {
+            MenuPage::Title => &[
+                MenuItem::NewGame,
+                MenuItem::Mode,
+                MenuItem::HighScores,
+                MenuItem::Settings,
+                MenuItem::Quit,
+            ],
+            MenuPage::Pause => &[
+                MenuItem::Resume,
+                MenuItem::Restart,
+                MenuItem::Settings,
+                MenuItem::Quit,
+            ],
+            MenuPage::Settings => &[MenuItem::Fullscreen, MenuItem::Back],
+            MenuPage::HighScores => &[MenuItem::Back],
+        }
+    }
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuItem {
+    NewGame,
+    Mode,
+    HighScores,
+    Resume,
+    Restart,
+    Settings,
+    Quit,
+    Fullscreen,
+    Back,
+}
+
+/// The mouse moving onto a menu item, or clicking it if `pressed`.
+///
+/// Sent as an event rather than read from `Interaction` directly, so replays can record it.
+#[derive(Event, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MenuPointer {
+    pub item: MenuItem,
+    pub pressed: bool,
+}
+
+/// Root of the menu UI.
+#[derive(Component)]
+struct MenuNode;
+
+fn open_main_menu(mut commands: Commands, mut menu: ResMut<Menu>, high_scores: Res<HighScores>) {
+    *menu = Menu::open(MenuPage::Title);
+    spawn_menu(&mut commands, menu.page, &high_scores);
+}
+
+fn open_pause_menu(mut commands: Commands, mut menu: ResMut<Menu>, high_scores: Res<HighScores>) {
+    *menu = Menu::open(MenuPage::Pause);
+    spawn_menu(&mut commands, menu.page, &high_scores);
+}
+
+/// Spawns a page of the menu, the item labels are filled in by [`refresh_menu_items`].
+fn spawn_menu(commands: &mut Commands, page: MenuPage, high_scores: &HighScores) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            MenuNode,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(page.title()),
+                TextFont {
+                    font_size: 50.0,
+                    ..default()
+                },
+                TextColor(WHITE_COLOR),
+            ));
+
+            if page == MenuPage::HighScores {
+                // practice runs never end, so they have no score to beat
+                for mode in [GameMode::Classic, GameMode::TimeAttack] {
+                    let best = high_scores
+                        .best(mode)
+                        .map_or("-".to_string(), |score| score.to_string());
+                    parent.spawn((
+                        Text::new(format!("{}: {best}", mode.name())),
+                        TextFont {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                        TextColor(WHITE_COLOR),
+                    ));
+                }
+            }
+
+            for item in page.items() {
+                parent.spawn((
+                    Button,
+                    Text::default(),
+                    TextFont {
+                        font_size: 30.0,
+                        ..default()
+                    },
+                    TextColor(WHITE_COLOR),
+                    *item,
+                ));
+            }
+        });
+}
+
+fn item_label(item: MenuItem, window: Option<&Window>, mode: GameMode) -> String {
+    match item {
+        MenuItem::NewGame => "New Game".into(),
+        MenuItem::Mode => format!("Mode: {}", mode.name()),
+        MenuItem::HighScores => "High Scores".into(),
+        MenuItem::Resume => "Resume".into(),
+        MenuItem::Restart => "Restart".into(),
+        MenuItem::Settings => "Settings".into(),
+        MenuItem::Quit => "Quit".into(),
+        MenuItem::Fullscreen => {
+            let fullscreen = window.is_some_and(|window| window.mode != WindowMode::Windowed);
+            format!("Fullscreen: {}", if fullscreen { "On" } else { "Off" })
+        }
+        MenuItem::Back => "Back".into(),
+    }
+}
+
+/// Turns mouse interactions with the menu items into [`MenuPointer`] events.
+pub fn point_at_menu(
+    buttons: Query<(&Interaction, &MenuItem), Changed<Interaction>>,
+    mut pointer_events: EventWriter<MenuPointer>,
+) {
+    for (interaction, item) in buttons.iter() {
+        let pressed = match interaction {
+            Interaction::Pressed => true,
+            Interaction::Hovered => false,
+            Interaction::None => continue,
+        };
+        pointer_events.write(MenuPointer {
+            item: *item,
+            pressed,
+        });
+    }
+}
+
+/// Moves the selection with the arrow keys or the mouse and activates it with Enter, Space or a click.
+#[allow(clippy::too_many_arguments)]
+fn navigate_menu(
+    mut commands: Commands,
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut pointer_events: EventReader<MenuPointer>,
+    menu_nodes: Query<Entity, With<MenuNode>>,
+    mut menu: ResMut<Menu>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut mode: ResMut<GameMode>,
+    high_scores: Res<HighScores>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    let items = menu.page.items();
+    let mut activated = None;
+
+    if kb_input.any_just_pressed([KeyCode::ArrowDown, KeyCode::KeyS]) {
+        menu.selected = (menu.selected + 1) % items.len();
+    } else if kb_input.any_just_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+        menu.selected = (menu.selected + items.len() - 1) % items.len();
+    } else if kb_input.any_just_pressed([KeyCode::Enter, KeyCode::Space]) {
+        activated = items.get(menu.selected).copied();
+    }
+
+    for pointer in pointer_events.read() {
+        if pointer.pressed {
+            activated = Some(pointer.item);
+        } else if let Some(index) = items.iter().position(|item| *item == pointer.item) {
+            menu.selected = index;
+        }
+    }
+
+    let Some(item) = activated else {
+        return;
+    };
+
+    let page = match item {
+        MenuItem::NewGame | MenuItem::Resume => {
+            next_state.set(GameState::InGame);
+            return;
+        }
+        MenuItem::Restart => {
+            commands.run_schedule(RestartRun);
+            next_state.set(GameState::InGame);
+            return;
+        }
+        MenuItem::Quit => {
+            exit_events.write(AppExit::Success);
+            return;
+        }
+        MenuItem::Mode => {
+            *mode = mode.next();
+            return;
+        }
+        MenuItem::Fullscreen => {
+            if let Ok(mut window) = windows.single_mut() {
+                window.mode = if window.mode == WindowMode::Windowed {
+                    WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+                } else {
+                    WindowMode::Windowed
+                };
+            }
+            return;
+        }
+        MenuItem::HighScores => MenuPage::HighScores,
+        MenuItem::Settings => MenuPage::Settings,
+        MenuItem::Back => menu.root,
+    };
+
+    for entity in menu_nodes.iter() {
+        commands.entity(entity).despawn();
+    }
+    menu.page = page;
+    menu.selected = 0;
+    spawn_menu(&mut commands, page, &high_scores);
+}
+
+/// Keeps the labels in sync with the settings they show and highlights the selected item.
+fn refresh_menu_items(
+    menu: Res<Menu>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mode: Res<GameMode>,
+    mut items: Query<(&MenuItem, &mut Text, &mut TextColor)>,
+) {
+    let selected = menu.page.items().get(menu.selected);
+    for (item, mut text, mut color) in items.iter_mut() {
+        let label = item_label(*item, windows.single().ok(), *mode);
+        if text.0 != label {
+            text.0 = label;
+        }
+
+        color.0 = if Some(item) == selected {
+            SELECTED_COLOR
+        } else {
+            WHITE_COLOR
+        };
+    }
+}
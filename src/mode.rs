@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+
+use crate::ui::{GameState, RestartRun, init_game_state};
+
+/// How long a time attack run lasts.
+pub const TIME_ATTACK_DURATION_S: f32 = 120.0;
+
+/// Rules a run is played by.
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GameMode {
+    /// Play until the ship runs out of health.
+    #[default]
+    Classic,
+    /// Asteroids don't hurt, for getting used to the controls.
+    Practice,
+    /// Score as much as possible before the clock runs out.
+    TimeAttack,
+}
+
+impl GameMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Classic => "Classic",
+            GameMode::Practice => "Practice",
+            GameMode::TimeAttack => "Time Attack",
+        }
+    }
+
+    /// The mode after this one when cycling through them in the main menu.
+    pub fn next(self) -> Self {
+        match self {
+            GameMode::Classic => GameMode::Practice,
+            GameMode::Practice => GameMode::TimeAttack,
+            GameMode::TimeAttack => GameMode::Classic,
+        }
+    }
+}
+
+/// Time left in a [`GameMode::TimeAttack`] run.
+#[derive(Resource)]
+pub struct TimeAttackClock(pub Timer);
+
+impl Default for TimeAttackClock {
+    fn default() -> Self {
+        Self(Timer::from_seconds(TIME_ATTACK_DURATION_S, TimerMode::Once))
+    }
+}
+
+/// Rules that differ between game modes and aren't part of any other plugin.
+pub struct GameModePlugin;
+
+impl Plugin for GameModePlugin {
+    fn build(&self, app: &mut App) {
+        init_game_state(app);
+
+        app.init_resource::<GameMode>()
+            .init_resource::<TimeAttackClock>()
+            .add_systems(
+                FixedUpdate,
+                tick_time_attack_clock
+                    .run_if(in_state(GameState::InGame).and(resource_equals(GameMode::TimeAttack))),
+            )
+            .add_systems(RestartRun, reset_time_attack_clock);
+    }
+}
+
+fn tick_time_attack_clock(
+    mut clock: ResMut<TimeAttackClock>,
+    time: Res<Time>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if clock.0.tick(time.delta()).just_finished() {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+fn reset_time_attack_clock(mut clock: ResMut<TimeAttackClock>) {
+    clock.0.reset();
+}
@@ -0,0 +1,65 @@
+use bevy::{prelude::*, window::WindowFocused};
+use bevy_rapier2d::prelude::*;
+
+use crate::ui::{GameState, init_game_state};
+
+/// Pausing a run with Escape or P, or when the window loses focus.
+/// The menu shown meanwhile is part of the [`MenuPlugin`](crate::MenuPlugin).
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        init_game_state(app);
+
+        // there are no window events when running headless
+        app.add_event::<WindowFocused>()
+            .add_systems(
+                Update,
+                (
+                    toggle_pause
+                        .run_if(in_state(GameState::InGame).or(in_state(GameState::Paused))),
+                    pause_on_focus_loss.run_if(in_state(GameState::InGame)),
+                ),
+            )
+            .add_systems(OnEnter(GameState::Paused), freeze_physics)
+            .add_systems(OnExit(GameState::Paused), unfreeze_physics);
+    }
+}
+
+fn toggle_pause(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !kb_input.any_just_pressed([KeyCode::Escape, KeyCode::KeyP]) {
+        return;
+    }
+
+    if *state.get() == GameState::Paused {
+        next_state.set(GameState::InGame);
+    } else {
+        next_state.set(GameState::Paused);
+    }
+}
+
+/// Pauses when the window loses focus, e.g. when alt-tabbing out.
+fn pause_on_focus_loss(
+    mut focus_events: EventReader<WindowFocused>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if focus_events.read().any(|event| !event.focused) {
+        next_state.set(GameState::Paused);
+    }
+}
+
+fn freeze_physics(mut configs: Query<&mut RapierConfiguration>) {
+    for mut config in configs.iter_mut() {
+        config.physics_pipeline_active = false;
+    }
+}
+
+fn unfreeze_physics(mut configs: Query<&mut RapierConfiguration>) {
+    for mut config in configs.iter_mut() {
+        config.physics_pipeline_active = true;
+    }
+}
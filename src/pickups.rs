@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    collisions::{GROUP_PICKUP, GROUP_PLAYER},
+    despawn_all,
+    interpolation::Interpolated,
+    rocks::rasterize,
+    ui::{GameState, RestartRun, init_game_state},
+    wrap::ScreenWrap,
+};
+
+/// How long a pickup floats around before it's gone.
+const PICKUP_S: f32 = 8.0;
+
+/// Pickups blink for this long before they're gone.
+const EXPIRY_WARNING_S: f32 = 2.0;
+
+/// Blinks per second of a pickup that's about to be gone.
+const BLINK_HZ: f32 = 6.0;
+
+const PICKUP_RADIUS: f32 = 4.0;
+
+const PICKUP_COLOR: Color = Color::srgb(0.8, 0.5, 1.0);
+
+/// Pickups left behind by crystal asteroids, collected in the
+/// [`collision_system`](crate::collisions::collision_system).
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        init_game_state(app);
+
+        app.add_systems(
+            FixedUpdate,
+            expire_pickups
+                .run_if(not(in_state(GameState::Paused)))
+                .before(PhysicsSet::SyncBackend),
+        )
+        .add_systems(RestartRun, despawn_all::<Pickup>)
+        .add_systems(OnEnter(GameState::MainMenu), despawn_all::<Pickup>);
+    }
+}
+
+#[derive(Component)]
+pub struct Pickup {
+    /// Health the ship gets back when it flies through.
+    pub heal: f32,
+    /// Runs until the pickup is gone.
+    pub timer: Timer,
+}
+
+/// Spawns a pickup drifting along with `linvel`.
+pub fn spawn_pickup(
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    pos: Vec3,
+    linvel: Vec2,
+    heal: f32,
+) -> Entity {
+    let diamond = Collider::convex_hull(&[
+        Vec2::new(PICKUP_RADIUS, 0.0),
+        Vec2::new(0.0, PICKUP_RADIUS),
+        Vec2::new(-PICKUP_RADIUS, 0.0),
+        Vec2::new(0.0, -PICKUP_RADIUS),
+    ])
+    .expect("a diamond spans an area");
+    let image = rasterize(&diamond, PICKUP_RADIUS, 1.0);
+
+    let transform = Transform::from_translation(pos);
+    commands
+        .spawn((
+            Sprite {
+                color: PICKUP_COLOR,
+                ..Sprite::from_image(images.add(image))
+            },
+            transform,
+            Interpolated::from(transform),
+            RigidBody::Dynamic,
+            Velocity::linear(linvel),
+            GravityScale(0.0),
+            Sleeping::disabled(),
+            diamond,
+            ColliderScale::Absolute(Vect::ONE),
+            Sensor,
+            ActiveEvents::COLLISION_EVENTS,
+            CollisionGroups::new(
+                Group::from_bits_truncate(GROUP_PICKUP),
+                Group::from_bits_truncate(GROUP_PLAYER),
+            ),
+            ScreenWrap::default(),
+            Pickup {
+                heal,
+                timer: Timer::from_seconds(PICKUP_S, TimerMode::Once),
+            },
+        ))
+        .id()
+}
+
+/// Removes pickups nobody collected in time, blinking for a while before.
+fn expire_pickups(
+    mut commands: Commands,
+    mut pickups: Query<(Entity, &mut Pickup, &mut Visibility)>,
+    time: Res<Time>,
+) {
+    for (entity, mut pickup, mut visibility) in pickups.iter_mut() {
+        if pickup.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let left = pickup.timer.remaining_secs();
+        let shown = left > EXPIRY_WARNING_S || (left * BLINK_HZ).fract() >= 0.5;
+        visibility.set_if_neq(if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
@@ -1,82 +1,198 @@
-use bevy::prelude::*;
+use bevy::{
+    app::{RunFixedMainLoop, RunFixedMainLoopSystem},
+    prelude::*,
+};
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-    RES_HEIGHT, RES_WIDTH,
-    collisions::{GROUP_ASTEROID, GROUP_PLAYER, GROUP_PROJECTILE},
-    get_high_res_size,
+    collisions::{GROUP_ASTEROID, GROUP_PICKUP, GROUP_PLAYER, GROUP_PROJECTILE},
+    despawn_all,
+    interpolation::Interpolated,
+    tuning::PlayerTuning,
+    ui::{GameState, RestartRun, init_game_state},
+    wrap::ScreenWrap,
 };
 
+/// Spawns the ship and handles flying it around.
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        init_game_state(app);
+
+        app.init_resource::<PlayerTuning>()
+            .init_resource::<PlayerInput>()
+            .add_systems(
+                RunFixedMainLoop,
+                read_player_input.in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    control_player.run_if(in_state(GameState::InGame)),
+                    tick_invulnerability.run_if(in_state(GameState::InGame)),
+                )
+                    .before(PhysicsSet::SyncBackend),
+            )
+            .add_systems(FixedPostUpdate, consume_player_input)
+            .add_systems(
+                RestartRun,
+                (despawn_all::<Player>, setup_player, reset_player_input).chain(),
+            )
+            .add_systems(OnExit(GameState::Paused), reset_player_input)
+            .add_systems(OnEnter(GameState::MainMenu), despawn_all::<Player>)
+            .add_systems(
+                Update,
+                apply_player_tuning.run_if(
+                    resource_changed::<PlayerTuning>.and(not(resource_added::<PlayerTuning>)),
+                ),
+            );
+    }
+}
+
 #[derive(Component)]
 pub struct Player;
 
+/// Blinks per second while the ship is [`Invulnerable`].
+const BLINK_HZ: f32 = 8.0;
+
+/// Contact forces below this don't count as a crash, like from an asteroid resting against
+/// the ship. In mass times canvas pixels per second squared.
+const MIN_CRASH_FORCE: f32 = 1.0;
+
+/// Keeps the ship from getting hurt again right after a hit.
+#[derive(Component)]
+pub struct Invulnerable {
+    pub timer: Timer,
+}
+
+impl Invulnerable {
+    pub fn new(duration_s: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(duration_s, TimerMode::Once),
+        }
+    }
+}
+
+/// Keyboard state for the next fixed step.
+///
+/// Presses are kept until a fixed step has seen them,
+/// so none get lost or handled twice no matter how many steps a frame runs.
+#[derive(Resource, Default)]
+pub struct PlayerInput {
+    /// 1 to turn left, -1 to turn right.
+    pub turn: f32,
+    pub thrust: bool,
+    pub fire: bool,
+}
+
 pub fn setup_player(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    window: Single<&Window>,
+    tuning: Res<PlayerTuning>,
 ) {
+    let transform = Transform::from_xyz(0.0, 0.0, 0.0).with_scale(Vec3::splat(1.0 / 40.0));
     commands.spawn((
         Sprite::from_image(asset_server.load("ship.png")),
-        Transform::from_xyz(0.0, 0.0, 0.0).with_scale(Vec3::splat(1.0 / 40.0)),
+        transform,
+        Interpolated::from(transform),
         RigidBody::Dynamic,
         GravityScale(0.0),
         Velocity::default(),
         Damping {
-            linear_damping: 2.0,
-            angular_damping: 3.0,
+            linear_damping: tuning.linear_damping,
+            angular_damping: tuning.angular_damping,
         },
-        Collider::ball(9.0 * 10.0 * get_high_res_size(&window)),
+        Collider::ball(tuning.collider_radius),
+        // sized in canvas pixels, not relative to the sprite
+        ColliderScale::Absolute(Vect::ONE),
         Player,
-        ActiveEvents::COLLISION_EVENTS,
-        Ccd::enabled(),
+        ScreenWrap::default(),
+        // crashes hurt by how hard they are
+        ActiveEvents::COLLISION_EVENTS | ActiveEvents::CONTACT_FORCE_EVENTS,
+        ContactForceEventThreshold(MIN_CRASH_FORCE),
         CollisionGroups::new(
             Group::from_bits_truncate(GROUP_PLAYER),
-            Group::from_bits_truncate(GROUP_ASTEROID | GROUP_PROJECTILE),
+            Group::from_bits_truncate(GROUP_ASTEROID | GROUP_PROJECTILE | GROUP_PICKUP),
         ),
     ));
 }
 
-const TURN_SPEED: f32 = 0.5;
-const MAX_TURN_SPEED: f32 = 3.0;
-const THRUST: f32 = 600.0;
+fn read_player_input(kb_input: Res<ButtonInput<KeyCode>>, mut input: ResMut<PlayerInput>) {
+    input.turn = if kb_input.any_pressed([KeyCode::ArrowRight, KeyCode::KeyD]) {
+        -1.0
+    } else if kb_input.any_pressed([KeyCode::ArrowLeft, KeyCode::KeyA]) {
+        1.0
+    } else {
+        0.0
+    };
+    input.thrust = kb_input.any_pressed([KeyCode::ArrowUp, KeyCode::KeyW]);
+    input.fire |= kb_input.just_pressed(KeyCode::Space);
+}
+
+fn consume_player_input(mut input: ResMut<PlayerInput>) {
+    input.fire = false;
+}
+
+/// Forgets the key press that restarted the run, so it doesn't fire a shot.
+fn reset_player_input(mut input: ResMut<PlayerInput>) {
+    *input = PlayerInput::default();
+}
 
 /// Controls player
 pub fn control_player(
     time: Res<Time>,
     mut velocity: Query<&mut Velocity, With<Player>>,
     mut transforms: Query<&mut Transform, With<Player>>,
-    kb_input: Res<ButtonInput<KeyCode>>,
+    input: Res<PlayerInput>,
+    tuning: Res<PlayerTuning>,
 ) {
     if let Ok(mut vel) = velocity.single_mut()
         && let Ok(transform) = transforms.single_mut()
     {
-        if kb_input.pressed(KeyCode::ArrowRight) || kb_input.pressed(KeyCode::KeyD) {
-            vel.angvel = (vel.angvel - TURN_SPEED).max(-MAX_TURN_SPEED);
-        } else if kb_input.pressed(KeyCode::ArrowLeft) || kb_input.pressed(KeyCode::KeyA) {
-            vel.angvel = (vel.angvel + TURN_SPEED).min(MAX_TURN_SPEED);
+        if input.turn != 0.0 {
+            vel.angvel = (vel.angvel + input.turn * tuning.turn_speed)
+                .clamp(-tuning.max_turn_speed, tuning.max_turn_speed);
         }
 
         // Forward thrust
-        if kb_input.pressed(KeyCode::ArrowUp) || kb_input.pressed(KeyCode::KeyW) {
+        if input.thrust {
             let direction = transform.up();
-            vel.linvel += direction.xy() * THRUST * time.delta_secs();
+            vel.linvel += direction.xy() * tuning.thrust * time.delta_secs();
         }
     }
 }
 
-pub fn keep_player(mut transform: Query<&mut Transform, With<Player>>) {
-    if let Ok(mut trans) = transform.single_mut() {
-        if trans.translation.x > (RES_WIDTH / 2) as f32 {
-            trans.translation.x = -((RES_WIDTH as f32) / 2.0);
-        }
-        if trans.translation.x < -((RES_WIDTH as f32) / 2.0) {
-            trans.translation.x = (RES_WIDTH / 2) as f32;
-        }
-        if trans.translation.y > (RES_HEIGHT / 2) as f32 {
-            trans.translation.y = -((RES_HEIGHT as f32) / 2.0);
-        }
-        if trans.translation.y < -((RES_HEIGHT as f32) / 2.0) {
-            trans.translation.y = (RES_HEIGHT / 2) as f32;
+/// Blinks the ship while it's invulnerable and makes it vulnerable again once the time is up.
+fn tick_invulnerability(
+    mut commands: Commands,
+    mut players: Query<(Entity, &mut Invulnerable, &mut Visibility)>,
+    time: Res<Time>,
+) {
+    for (entity, mut invulnerable, mut visibility) in players.iter_mut() {
+        if invulnerable.timer.tick(time.delta()).finished() {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<Invulnerable>();
+            continue;
         }
+
+        let blink = (invulnerable.timer.elapsed_secs() * BLINK_HZ * 2.0) as u32;
+        *visibility = if blink.is_multiple_of(2) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+/// Updates the ship when its tuning changed while the game is running.
+fn apply_player_tuning(
+    mut players: Query<(&mut Damping, &mut Collider), With<Player>>,
+    tuning: Res<PlayerTuning>,
+) {
+    for (mut damping, mut collider) in players.iter_mut() {
+        damping.linear_damping = tuning.linear_damping;
+        damping.angular_damping = tuning.angular_damping;
+        *collider = Collider::ball(tuning.collider_radius);
     }
 }
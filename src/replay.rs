@@ -0,0 +1,221 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{input::InputSystem, prelude::*, time::TimeUpdateStrategy, window::WindowFocused};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collisions::Stats,
+    menu::{MenuPointer, point_at_menu},
+    rng::GameRng,
+    ui::health_text,
+};
+
+/// A whole recorded session: the seed it started with and the input of every frame.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Replay {
+    pub seed: u64,
+    pub frames: Vec<ReplayFrame>,
+}
+
+/// Input as seen by gameplay systems during a single frame.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ReplayFrame {
+    /// Real time that passed since the previous frame, in nanoseconds.
+    pub delta_ns: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pressed: Vec<KeyCode>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub just_pressed: Vec<KeyCode>,
+    /// Mouse hovers and clicks on menu items.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub menu: Vec<MenuPointer>,
+    /// The window lost focus, which pauses the game.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub focus_lost: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "failed to access replay file: {e}"),
+            ReplayError::Parse(e) => write!(f, "invalid replay file: {e}"),
+            ReplayError::Serialize(e) => write!(f, "failed to serialize replay: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        let content = fs::read_to_string(path).map_err(ReplayError::Io)?;
+        ron::from_str(&content).map_err(ReplayError::Parse)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        let content = ron::to_string(self).map_err(ReplayError::Serialize)?;
+        fs::write(path, content).map_err(ReplayError::Io)
+    }
+}
+
+/// Records the input of the whole session and writes it to `path` on exit.
+pub struct RecordPlugin {
+    pub path: PathBuf,
+}
+
+impl Plugin for RecordPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameRng>()
+            .insert_resource(Recorder {
+                path: self.path.clone(),
+                replay: Replay::default(),
+            })
+            .add_event::<WindowFocused>()
+            .add_event::<MenuPointer>()
+            .add_systems(Startup, record_seed)
+            .add_systems(
+                PreUpdate,
+                record_input.after(InputSystem).after(point_at_menu),
+            )
+            .add_systems(Last, save_recording);
+    }
+}
+
+/// Feeds a recorded session to the game instead of the keyboard, mouse and window and exits once it's over.
+pub struct ReplayPlugin {
+    pub replay: Replay,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GameRng::new(self.replay.seed))
+            .insert_resource(Playback {
+                replay: self.replay.clone(),
+                frame: 0,
+            })
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO))
+            .add_event::<WindowFocused>()
+            .add_event::<MenuPointer>()
+            .add_systems(
+                PreUpdate,
+                play_back_input.after(InputSystem).after(point_at_menu),
+            );
+    }
+}
+
+#[derive(Resource)]
+struct Recorder {
+    path: PathBuf,
+    replay: Replay,
+}
+
+#[derive(Resource)]
+struct Playback {
+    replay: Replay,
+    frame: usize,
+}
+
+fn record_seed(mut recorder: ResMut<Recorder>, rng: Res<GameRng>) {
+    recorder.replay.seed = rng.seed();
+}
+
+fn record_input(
+    mut recorder: ResMut<Recorder>,
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut pointer_events: EventReader<MenuPointer>,
+    mut focus_events: EventReader<WindowFocused>,
+    time: Res<Time<Real>>,
+) {
+    recorder.replay.frames.push(ReplayFrame {
+        delta_ns: time.delta().as_nanos() as u64,
+        pressed: kb_input.get_pressed().copied().collect(),
+        just_pressed: kb_input.get_just_pressed().copied().collect(),
+        menu: pointer_events.read().copied().collect(),
+        focus_lost: focus_events.read().any(|event| !event.focused),
+    });
+}
+
+fn save_recording(mut exit_events: EventReader<AppExit>, recorder: Res<Recorder>) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+
+    match recorder.replay.save(&recorder.path) {
+        Ok(()) => info!(
+            "saved replay with {} frames to {}",
+            recorder.replay.frames.len(),
+            recorder.path.display()
+        ),
+        Err(e) => error!("{e}"),
+    }
+}
+
+/// Overwrites the keyboard state, menu clicks and focus changes with the current frame of the replay
+/// and makes the next frame advance time by exactly the recorded amount.
+fn play_back_input(
+    mut playback: ResMut<Playback>,
+    mut kb_input: ResMut<ButtonInput<KeyCode>>,
+    mut pointer_events: ResMut<Events<MenuPointer>>,
+    mut focus_events: ResMut<Events<WindowFocused>>,
+    mut time_strategy: ResMut<TimeUpdateStrategy>,
+    mut exit_events: EventWriter<AppExit>,
+    stats: Option<Res<Stats>>,
+) {
+    let Some(frame) = playback.replay.frames.get(playback.frame) else {
+        if let Some(stats) = stats {
+            info!(
+                "replay finished with score {} and health {}",
+                stats.score,
+                health_text(stats.health)
+            );
+        }
+        exit_events.write(AppExit::Success);
+        return;
+    };
+
+    kb_input.reset_all();
+    for key in &frame.pressed {
+        kb_input.press(*key);
+        if !frame.just_pressed.contains(key) {
+            kb_input.clear_just_pressed(*key);
+        }
+    }
+    // pressed and released again within the same frame
+    for key in &frame.just_pressed {
+        if !frame.pressed.contains(key) {
+            kb_input.press(*key);
+            kb_input.release(*key);
+            kb_input.clear_just_released(*key);
+        }
+    }
+
+    pointer_events.clear();
+    pointer_events.send_batch(frame.menu.iter().copied());
+    focus_events.clear();
+    if frame.focus_lost {
+        focus_events.send(WindowFocused {
+            window: Entity::PLACEHOLDER,
+            focused: false,
+        });
+    }
+
+    playback.frame += 1;
+    if let Some(next) = playback.replay.frames.get(playback.frame) {
+        *time_strategy = TimeUpdateStrategy::ManualDuration(Duration::from_nanos(next.delta_ns));
+    }
+}
@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+use rand::{RngCore, SeedableRng, rngs::StdRng};
+
+/// The only source of randomness for gameplay systems.
+///
+/// Every run starts from a known seed so it can be reproduced given the same inputs.
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Seed the current run was started with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Starts over from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+
+    /// Starts over from the seed of the current run, forgetting everything drawn so far.
+    pub fn restart(&mut self) {
+        self.reseed(self.seed);
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        self.rng.fill_bytes(dst)
+    }
+}
@@ -0,0 +1,88 @@
+use std::{f32::consts::TAU, ops::Range};
+
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::rng::GameRng;
+
+/// A random lumpy outline around the origin, going counterclockwise.
+/// Every corner is at most `radius` away, and up to `lumpiness` times that closer in.
+pub fn lumpy_outline(
+    rng: &mut GameRng,
+    radius: f32,
+    lumpiness: f32,
+    corners: Range<u32>,
+) -> Vec<Vec2> {
+    let count = rng.random_range(corners);
+    let step = TAU / count as f32;
+
+    (0..count)
+        .map(|i| {
+            // off the even spacing, but never past a neighbour
+            let angle = step * (i as f32 + rng.random_range(-0.3..0.3));
+            let distance = radius * (1.0 - lumpiness * rng.random::<f32>());
+            Vec2::from_angle(angle) * distance
+        })
+        .collect()
+}
+
+/// Collider exactly covering an outline that every ray from the origin crosses only once,
+/// made of the triangles between the origin and each edge.
+pub fn outline_collider(outline: &[Vec2]) -> Collider {
+    let triangles = outline
+        .iter()
+        .zip(outline.iter().cycle().skip(1))
+        .map(|(a, b)| (Vect::ZERO, 0.0, Collider::triangle(Vect::ZERO, *a, *b)))
+        .collect();
+    Collider::compound(triangles)
+}
+
+/// Draws a collider into a pixel-art image with one texel per canvas pixel,
+/// with the collider's origin in its center. `radius` is how far the collider reaches out.
+pub fn rasterize(collider: &Collider, radius: f32, shade: f32) -> Image {
+    let side = (radius * 2.0).ceil() as u32 + 2;
+    let inside = |x: i64, y: i64| {
+        if x < 0 || y < 0 || x >= side as i64 || y >= side as i64 {
+            return false;
+        }
+        let point = Vec2::new(
+            x as f32 + 0.5 - side as f32 / 2.0,
+            side as f32 / 2.0 - (y as f32 + 0.5),
+        );
+        collider.contains_point(Vect::ZERO, 0.0, point)
+    };
+
+    let mut data = Vec::with_capacity((side * side * 4) as usize);
+    for y in 0..side as i64 {
+        for x in 0..side as i64 {
+            let color: [f32; 4] = if !inside(x, y) {
+                [0.0; 4]
+            } else if !inside(x + 1, y) || !inside(x, y + 1) {
+                // outline, lit from the top left
+                [shade * 0.5, shade * 0.5, shade * 0.55, 1.0]
+            } else if !inside(x - 1, y) || !inside(x, y - 1) {
+                [shade * 1.3, shade * 1.3, shade * 1.25, 1.0]
+            } else {
+                [shade, shade, shade * 0.95, 1.0]
+            };
+            data.extend(color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0) as u8));
+        }
+    }
+
+    Image::new(
+        Extent3d {
+            width: side,
+            height: side,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
@@ -2,98 +2,213 @@ use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-    RES_HEIGHT, RES_WIDTH,
     collisions::{GROUP_ASTEROID, GROUP_PROJECTILE},
-    get_high_res_size,
-    player::Player,
+    despawn_all,
+    interpolation::Interpolated,
+    player::{Player, PlayerInput},
+    tuning::WeaponTuning,
+    ui::{GameState, RestartRun, init_game_state},
+    wrap::ScreenWrap,
 };
 
+/// Share of its range a projectile fades out over at the end.
+const FADE_SHARE: f32 = 0.3;
+
+/// How long the puff of a projectile running out of range lasts.
+const FIZZLE_S: f32 = 0.25;
+
+const PROJECTILE_SCALE: f32 = 1.0 / 5.0;
+
+/// How far ahead projectiles look for what they're about to hit, in canvas pixels.
+/// More than a projectile fired from a ship at full speed moves in a step.
+const CCD_PREDICTION: f32 = 12.0;
+
+/// Firing projectiles and cleaning them up.
+pub struct ShootingPlugin;
+
+impl Plugin for ShootingPlugin {
+    fn build(&self, app: &mut App) {
+        init_game_state(app);
+
+        app.init_resource::<WeaponTuning>()
+            .init_resource::<ProjectilesData>()
+            .init_resource::<PlayerInput>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    shoot.run_if(in_state(GameState::InGame)),
+                    manage_projectiles.run_if(not(in_state(GameState::Paused))),
+                )
+                    .before(PhysicsSet::SyncBackend),
+            )
+            .add_systems(
+                RestartRun,
+                (
+                    despawn_all::<Projectile>,
+                    despawn_all::<Fizzle>,
+                    reset_projectiles_data,
+                ),
+            )
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                (despawn_all::<Projectile>, despawn_all::<Fizzle>),
+            )
+            .add_systems(
+                Update,
+                (
+                    animate_fizzles.run_if(not(in_state(GameState::Paused))),
+                    apply_weapon_tuning.run_if(
+                        resource_changed::<WeaponTuning>.and(not(resource_added::<WeaponTuning>)),
+                    ),
+                ),
+            );
+    }
+}
+
 #[derive(Component)]
 pub struct Projectile {
-    initial_velocity: Vec2,
+    /// Distance flown so far, in canvas pixels.
+    pub travelled: f32,
+    /// Distance after which it's gone, taken from the weapon it was fired with.
+    pub range: f32,
+    /// Health it takes off the asteroid it hits, also from the weapon.
+    pub damage: f32,
 }
 
-#[derive(Resource)]
-pub struct ProjectilesData {
-    pub last_shoot: f32,
+impl Projectile {
+    pub fn new(range: f32, damage: f32) -> Self {
+        Self {
+            travelled: 0.0,
+            range,
+            damage,
+        }
+    }
 }
 
-const SHOOT_STRENGTH: f32 = 200.0;
-const MAX_SHOOT_DELTA_S: f32 = 0.2;
+/// Short puff left behind where a projectile ran out of range.
+#[derive(Component)]
+pub struct Fizzle {
+    timer: Timer,
+}
+
+#[derive(Resource, Default)]
+pub struct ProjectilesData {
+    /// Time until the next shot, only runs down while the game isn't paused.
+    pub cooldown: Timer,
+}
 
 // TODO: sound
 pub fn shoot(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    kb_input: Res<ButtonInput<KeyCode>>,
+    input: Res<PlayerInput>,
     player_transform: Query<(&Transform, &Velocity), With<Player>>,
-    window: Single<&Window>,
     mut proj_data: ResMut<ProjectilesData>,
     time: Res<Time>,
+    tuning: Res<WeaponTuning>,
 ) {
-    if kb_input.just_pressed(KeyCode::Space)
+    proj_data.cooldown.tick(time.delta());
+
+    if input.fire
         && let Ok((trans, vel)) = player_transform.single()
-        && time.elapsed_secs() - proj_data.last_shoot > MAX_SHOOT_DELTA_S
+        && proj_data.cooldown.finished()
     {
         let rotated: Vec3 =
             (trans.rotation * Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)) * Vec3::X;
-        let velocity = Vec2::new(rotated.x, rotated.y) * SHOOT_STRENGTH + vel.linvel;
+        let velocity = Vec2::new(rotated.x, rotated.y) * tuning.shoot_strength + vel.linvel;
+        let transform = Transform::from_xyz(trans.translation.x, trans.translation.y, 0.0)
+            .with_rotation(trans.rotation)
+            .with_scale(Vec3::splat(PROJECTILE_SCALE));
         commands.spawn((
             Sprite::from_image(asset_server.load("proj.png")),
-            Transform::from_xyz(trans.translation.x, trans.translation.y, 0.0)
-                .with_rotation(trans.rotation)
-                .with_scale(Vec3::splat(1.0 / 5.0)),
+            transform,
+            Interpolated::from(transform),
             RigidBody::Dynamic,
             Velocity::linear(velocity),
             Sleeping::disabled(),
-            Projectile {
-                initial_velocity: velocity,
-            },
+            Projectile::new(tuning.range, tuning.damage),
+            ScreenWrap::default(),
             GravityScale(0.0),
-            Collider::ball(2.0 * get_high_res_size(&window)),
-            ActiveEvents::COLLISION_EVENTS,
-            Ccd::enabled(),
-            CollisionGroups::new(
-                Group::from_bits_truncate(GROUP_PROJECTILE),
-                Group::from_bits_truncate(GROUP_ASTEROID),
+            (
+                Collider::ball(tuning.projectile_radius),
+                ColliderScale::Absolute(Vect::ONE),
+                ActiveEvents::COLLISION_EVENTS,
+                CollisionGroups::new(
+                    Group::from_bits_truncate(GROUP_PROJECTILE),
+                    Group::from_bits_truncate(GROUP_ASTEROID),
+                ),
+                // several times its own size per step, it would fly through small fragments.
+                // Not full CCD: parry2d 0.22.1 panics building the CCD tree (`Bvh::from_iter`)
+                // once only one or two such bodies are left, like when returning to the menu.
+                SoftCcd {
+                    prediction: CCD_PREDICTION,
+                },
             ),
         ));
 
-        proj_data.last_shoot = time.elapsed_secs();
+        proj_data.cooldown = Timer::from_seconds(tuning.max_shoot_delta_s, TimerMode::Once);
     }
 }
 
+fn reset_projectiles_data(mut proj_data: ResMut<ProjectilesData>) {
+    *proj_data = ProjectilesData::default();
+}
+
+/// Fades projectiles out towards the end of their range and replaces them with a [`Fizzle`] there.
 pub fn manage_projectiles(
     mut commands: Commands,
-    transform: Query<&Transform, With<Projectile>>,
-    entity: Query<Entity, With<Projectile>>,
-    velocity_query: Query<(&Velocity, &Projectile), With<Projectile>>,
+    mut projectiles: Query<(Entity, &mut Projectile, &Velocity, &Transform, &mut Sprite)>,
+    time: Res<Time>,
 ) {
-    for (trans, ent) in transform.iter().zip(entity.iter()) {
-        if trans.translation.x > (RES_WIDTH / 2) as f32 {
-            commands.entity(ent).despawn();
-        }
-        if trans.translation.x < -((RES_WIDTH as f32) / 2.0) {
-            commands.entity(ent).despawn();
-        }
-        if trans.translation.y > (RES_HEIGHT / 2) as f32 {
-            commands.entity(ent).despawn();
+    for (entity, mut projectile, velocity, transform, mut sprite) in projectiles.iter_mut() {
+        // summed up instead of measured from the muzzle, which could be on the other side of the canvas by now
+        projectile.travelled += velocity.linvel.length() * time.delta_secs();
+
+        let left = projectile.range - projectile.travelled;
+        if left <= 0.0 {
+            commands.entity(entity).despawn();
+            commands.spawn((
+                sprite.clone(),
+                Transform::from_translation(transform.translation)
+                    .with_scale(Vec3::splat(PROJECTILE_SCALE)),
+                Fizzle {
+                    timer: Timer::from_seconds(FIZZLE_S, TimerMode::Once),
+                },
+            ));
+            continue;
         }
-        if trans.translation.y < -((RES_HEIGHT as f32) / 2.0) {
-            commands.entity(ent).despawn();
+
+        let alpha = (left / (projectile.range * FADE_SHARE)).min(1.0);
+        if sprite.color.alpha() != alpha {
+            sprite.color.set_alpha(alpha);
         }
     }
+}
 
-    // TODO: animation for despawn
-    for ((vel, proj), ent) in velocity_query.iter().zip(entity.iter()) {
-        if proj.initial_velocity.x.abs() * 0.8 > vel.linvel.x.abs()
-            || proj.initial_velocity.y.abs() * 0.8 > vel.linvel.y.abs()
-        {
-            commands.entity(ent).despawn();
-            info!(
-                "despawned projecticle with speed {:?} (spawned with {:?})",
-                vel.linvel, proj.initial_velocity
-            );
+/// Grows and fades out puffs until they're gone.
+fn animate_fizzles(
+    mut commands: Commands,
+    mut fizzles: Query<(Entity, &mut Fizzle, &mut Transform, &mut Sprite)>,
+    time: Res<Time>,
+) {
+    for (entity, mut fizzle, mut transform, mut sprite) in fizzles.iter_mut() {
+        if fizzle.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
         }
+
+        let t = fizzle.timer.fraction();
+        transform.scale = Vec3::splat(PROJECTILE_SCALE * (1.0 + 2.0 * t));
+        sprite.color.set_alpha(0.5 * (1.0 - t));
+    }
+}
+
+/// Resizes projectiles in flight when the weapon tuning changed while the game is running.
+fn apply_weapon_tuning(
+    mut projectiles: Query<&mut Collider, With<Projectile>>,
+    tuning: Res<WeaponTuning>,
+) {
+    for mut collider in projectiles.iter_mut() {
+        *collider = Collider::ball(tuning.projectile_radius);
     }
 }
@@ -0,0 +1,549 @@
+use std::{
+    fmt, fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{
+    asset::{
+        AssetLoader, AssetPath, LoadContext,
+        io::{AssetSource, Reader},
+    },
+    prelude::*,
+};
+use serde::Deserialize;
+
+/// Asset source the tuning file is watched through.
+const TUNING_SOURCE: &str = "tuning";
+
+/// Every balancing value of the game, as read from a tuning file.
+///
+/// Missing sections and fields fall back to the built-in defaults,
+/// so a tuning file only needs to contain the values it changes.
+#[derive(Asset, TypePath, Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tuning {
+    pub player: PlayerTuning,
+    pub weapon: WeaponTuning,
+    pub asteroids: AsteroidTuning,
+    pub waves: WaveTuning,
+    pub stats: StatsTuning,
+}
+
+/// How the ship flies.
+#[derive(Resource, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlayerTuning {
+    /// Angular velocity added per fixed step while turning.
+    pub turn_speed: f32,
+    pub max_turn_speed: f32,
+    pub thrust: f32,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    /// In canvas pixels, like every other size and speed.
+    pub collider_radius: f32,
+    /// How long the ship can't be hurt again after a hit, in seconds.
+    pub invulnerability_s: f32,
+    /// Speed the ship gets pushed away from an asteroid with when hit.
+    pub knockback_speed: f32,
+    /// Damage the ship takes in a crash per unit of impulse, which is mass times canvas pixels
+    /// per second. Heavy asteroids and fast crashes hurt more.
+    pub impact_damage: f32,
+}
+
+impl Default for PlayerTuning {
+    fn default() -> Self {
+        Self {
+            turn_speed: 0.5,
+            max_turn_speed: 3.0,
+            thrust: 600.0,
+            linear_damping: 2.0,
+            angular_damping: 3.0,
+            collider_radius: 9.0,
+            invulnerability_s: 2.0,
+            knockback_speed: 80.0,
+            impact_damage: 0.25,
+        }
+    }
+}
+
+/// How projectiles are fired.
+#[derive(Resource, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeaponTuning {
+    pub shoot_strength: f32,
+    /// Minimum time between two shots in seconds.
+    pub max_shoot_delta_s: f32,
+    /// In canvas pixels.
+    pub projectile_radius: f32,
+    /// How far a projectile flies before it's gone.
+    pub range: f32,
+    /// Health a projectile takes off the asteroid it hits.
+    pub damage: f32,
+}
+
+impl Default for WeaponTuning {
+    fn default() -> Self {
+        Self {
+            shoot_strength: 200.0,
+            max_shoot_delta_s: 0.2,
+            projectile_radius: 1.5,
+            range: 240.0,
+            damage: 1.0,
+        }
+    }
+}
+
+/// How big and fast asteroids are spawned and where.
+/// What they are like is up to their [kinds](crate::kinds::AsteroidKinds).
+#[derive(Resource, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct AsteroidTuning {
+    pub scale: Range<f32>,
+    /// Speed of asteroids in the first wave.
+    pub speed: Range<f32>,
+    pub angvel: Range<f32>,
+    /// Asteroids never appear closer to the ship than this.
+    pub safe_radius: f32,
+    /// Spawning waits while this many asteroids are around, fragments included.
+    pub max_alive: u32,
+}
+
+impl Default for AsteroidTuning {
+    fn default() -> Self {
+        Self {
+            scale: 0.3..0.6,
+            speed: 5.0..20.0,
+            angvel: -2.0..2.0,
+            safe_radius: 60.0,
+            max_alive: 24,
+        }
+    }
+}
+
+/// How many asteroids come in each wave and how that ramps up over a run.
+/// Changes apply from the next wave on.
+#[derive(Resource, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct WaveTuning {
+    /// Asteroids in the first wave.
+    pub first_wave: u32,
+    /// Asteroids added with every wave after it.
+    pub growth: u32,
+    /// Share of large asteroids in the first wave.
+    pub large_share: f32,
+    /// Added to the share of large asteroids with every wave.
+    pub large_share_growth: f32,
+    /// Time between two asteroids of the first wave, in seconds.
+    pub spawn_interval_s: f32,
+    /// The spawn interval gets multiplied with this with every wave.
+    pub spawn_interval_factor: f32,
+    pub min_spawn_interval_s: f32,
+    /// Asteroid speeds get multiplied with this with every wave.
+    pub speed_growth: f32,
+    pub max_speed_multiplier: f32,
+    /// Pause before a wave while it's announced, in seconds.
+    pub break_s: f32,
+    /// The next wave comes after this long even if asteroids are left, in seconds.
+    pub clear_timeout_s: f32,
+}
+
+impl Default for WaveTuning {
+    fn default() -> Self {
+        Self {
+            first_wave: 5,
+            growth: 2,
+            large_share: 0.2,
+            large_share_growth: 0.1,
+            spawn_interval_s: 1.0,
+            spawn_interval_factor: 0.9,
+            min_spawn_interval_s: 0.3,
+            speed_growth: 1.1,
+            max_speed_multiplier: 2.5,
+            break_s: 2.0,
+            clear_timeout_s: 15.0,
+        }
+    }
+}
+
+/// What a run starts with.
+#[derive(Resource, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatsTuning {
+    pub starting_health: f32,
+}
+
+impl Default for StatsTuning {
+    fn default() -> Self {
+        Self {
+            starting_health: 3.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TuningError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Invalid {
+        field: &'static str,
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for TuningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TuningError::Io(e) => write!(f, "failed to read tuning file: {e}"),
+            TuningError::Parse(e) => write!(f, "invalid tuning file: {e}"),
+            TuningError::Invalid { field, reason } => {
+                write!(f, "invalid tuning value `{field}`: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TuningError {}
+
+impl Tuning {
+    pub fn load(path: &Path) -> Result<Self, TuningError> {
+        let content = fs::read(path).map_err(TuningError::Io)?;
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &[u8]) -> Result<Self, TuningError> {
+        let tuning: Self = ron::de::from_bytes(content).map_err(TuningError::Parse)?;
+        tuning.validate()?;
+        Ok(tuning)
+    }
+
+    /// Rejects values the game can't work with.
+    pub fn validate(&self) -> Result<(), TuningError> {
+        fn check(ok: bool, field: &'static str, reason: &'static str) -> Result<(), TuningError> {
+            if ok {
+                Ok(())
+            } else {
+                Err(TuningError::Invalid { field, reason })
+            }
+        }
+
+        let Tuning {
+            player,
+            weapon,
+            asteroids,
+            waves,
+            stats,
+        } = self;
+
+        // infinite durations and ranges make timers and random numbers panic
+        for (value, field) in [
+            (player.turn_speed, "player.turn_speed"),
+            (player.max_turn_speed, "player.max_turn_speed"),
+            (player.thrust, "player.thrust"),
+            (player.linear_damping, "player.linear_damping"),
+            (player.angular_damping, "player.angular_damping"),
+            (player.collider_radius, "player.collider_radius"),
+            (player.invulnerability_s, "player.invulnerability_s"),
+            (player.knockback_speed, "player.knockback_speed"),
+            (player.impact_damage, "player.impact_damage"),
+            (weapon.shoot_strength, "weapon.shoot_strength"),
+            (weapon.max_shoot_delta_s, "weapon.max_shoot_delta_s"),
+            (weapon.projectile_radius, "weapon.projectile_radius"),
+            (weapon.range, "weapon.range"),
+            (weapon.damage, "weapon.damage"),
+            (asteroids.scale.start, "asteroids.scale"),
+            (asteroids.scale.end, "asteroids.scale"),
+            (asteroids.speed.start, "asteroids.speed"),
+            (asteroids.speed.end, "asteroids.speed"),
+            (asteroids.angvel.start, "asteroids.angvel"),
+            (asteroids.angvel.end, "asteroids.angvel"),
+            (asteroids.safe_radius, "asteroids.safe_radius"),
+            (waves.large_share, "waves.large_share"),
+            (waves.large_share_growth, "waves.large_share_growth"),
+            (waves.spawn_interval_s, "waves.spawn_interval_s"),
+            (waves.spawn_interval_factor, "waves.spawn_interval_factor"),
+            (waves.min_spawn_interval_s, "waves.min_spawn_interval_s"),
+            (waves.speed_growth, "waves.speed_growth"),
+            (waves.max_speed_multiplier, "waves.max_speed_multiplier"),
+            (waves.break_s, "waves.break_s"),
+            (waves.clear_timeout_s, "waves.clear_timeout_s"),
+            (stats.starting_health, "stats.starting_health"),
+        ] {
+            check(value.is_finite(), field, "must be a finite number")?;
+        }
+
+        check(
+            player.turn_speed >= 0.0,
+            "player.turn_speed",
+            "must not be negative",
+        )?;
+        check(
+            player.max_turn_speed > 0.0,
+            "player.max_turn_speed",
+            "must be positive",
+        )?;
+        check(
+            player.thrust >= 0.0,
+            "player.thrust",
+            "must not be negative",
+        )?;
+        check(
+            player.linear_damping >= 0.0,
+            "player.linear_damping",
+            "must not be negative",
+        )?;
+        check(
+            player.angular_damping >= 0.0,
+            "player.angular_damping",
+            "must not be negative",
+        )?;
+        check(
+            player.collider_radius > 0.0,
+            "player.collider_radius",
+            "must be positive",
+        )?;
+
+        check(
+            player.invulnerability_s >= 0.0,
+            "player.invulnerability_s",
+            "must not be negative",
+        )?;
+        check(
+            player.knockback_speed >= 0.0,
+            "player.knockback_speed",
+            "must not be negative",
+        )?;
+        check(
+            player.impact_damage >= 0.0,
+            "player.impact_damage",
+            "must not be negative",
+        )?;
+
+        check(
+            weapon.shoot_strength > 0.0,
+            "weapon.shoot_strength",
+            "must be positive",
+        )?;
+        check(
+            weapon.max_shoot_delta_s >= 0.0,
+            "weapon.max_shoot_delta_s",
+            "must not be negative",
+        )?;
+        check(
+            weapon.projectile_radius > 0.0,
+            "weapon.projectile_radius",
+            "must be positive",
+        )?;
+        check(weapon.range > 0.0, "weapon.range", "must be positive")?;
+        check(weapon.damage > 0.0, "weapon.damage", "must be positive")?;
+
+        check(
+            asteroids.scale.start > 0.0,
+            "asteroids.scale",
+            "must start above 0",
+        )?;
+        check(
+            !asteroids.scale.is_empty(),
+            "asteroids.scale",
+            "start must be below end",
+        )?;
+        check(
+            asteroids.speed.start >= 0.0,
+            "asteroids.speed",
+            "must not start below 0",
+        )?;
+        check(
+            !asteroids.speed.is_empty(),
+            "asteroids.speed",
+            "start must be below end",
+        )?;
+        check(
+            !asteroids.angvel.is_empty(),
+            "asteroids.angvel",
+            "start must be below end",
+        )?;
+
+        check(
+            asteroids.safe_radius >= 0.0,
+            "asteroids.safe_radius",
+            "must not be negative",
+        )?;
+        check(
+            asteroids.max_alive > 0,
+            "asteroids.max_alive",
+            "must be positive",
+        )?;
+
+        check(waves.first_wave > 0, "waves.first_wave", "must be positive")?;
+        check(
+            (0.0..=1.0).contains(&waves.large_share),
+            "waves.large_share",
+            "must be between 0 and 1",
+        )?;
+        check(
+            waves.large_share_growth >= 0.0,
+            "waves.large_share_growth",
+            "must not be negative",
+        )?;
+        check(
+            waves.spawn_interval_s > 0.0,
+            "waves.spawn_interval_s",
+            "must be positive",
+        )?;
+        check(
+            waves.spawn_interval_factor > 0.0,
+            "waves.spawn_interval_factor",
+            "must be positive",
+        )?;
+        check(
+            waves.min_spawn_interval_s > 0.0,
+            "waves.min_spawn_interval_s",
+            "must be positive",
+        )?;
+        check(
+            waves.speed_growth > 0.0,
+            "waves.speed_growth",
+            "must be positive",
+        )?;
+        check(
+            waves.max_speed_multiplier > 0.0,
+            "waves.max_speed_multiplier",
+            "must be positive",
+        )?;
+        check(
+            waves.break_s >= 0.0,
+            "waves.break_s",
+            "must not be negative",
+        )?;
+        check(
+            waves.clear_timeout_s >= 0.0,
+            "waves.clear_timeout_s",
+            "must not be negative",
+        )?;
+
+        check(
+            stats.starting_health > 0.0,
+            "stats.starting_health",
+            "must be positive",
+        )
+    }
+}
+
+/// Makes the values of a tuning file available to the gameplay plugins.
+///
+/// Given the `path` the tuning was loaded from, changes to that file are applied while the game is running.
+/// In that case it has to be added before the `AssetPlugin`, otherwise the file can't be watched.
+pub struct TuningPlugin {
+    pub tuning: Tuning,
+    pub path: Option<PathBuf>,
+}
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.tuning.player.clone())
+            .insert_resource(self.tuning.weapon.clone())
+            .insert_resource(self.tuning.asteroids.clone())
+            .insert_resource(self.tuning.waves.clone())
+            .insert_resource(self.tuning.stats.clone());
+
+        let Some(path) = &self.path else {
+            return;
+        };
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.clone());
+        let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+            warn!("can't watch tuning file {}", path.display());
+            return;
+        };
+
+        let dir = dir.to_string_lossy().into_owned();
+        app.register_asset_source(
+            TUNING_SOURCE,
+            AssetSource::build()
+                .with_reader(AssetSource::get_default_reader(dir.clone()))
+                .with_watcher(AssetSource::get_default_watcher(
+                    dir,
+                    Duration::from_millis(300),
+                )),
+        )
+        .insert_resource(TuningFile {
+            path: AssetPath::from_path(Path::new(file_name))
+                .with_source(TUNING_SOURCE)
+                .into_owned(),
+            handle: Handle::default(),
+        });
+    }
+
+    fn finish(&self, app: &mut App) {
+        if !app.world().contains_resource::<TuningFile>() {
+            return;
+        }
+
+        app.init_asset::<Tuning>()
+            .register_asset_loader(TuningLoader)
+            .add_systems(Startup, watch_tuning_file)
+            .add_systems(Update, apply_tuning_changes);
+    }
+}
+
+/// The tuning file being watched, kept loaded so it gets reloaded on changes.
+#[derive(Resource)]
+struct TuningFile {
+    path: AssetPath<'static>,
+    handle: Handle<Tuning>,
+}
+
+/// Loads and validates tuning files through the asset server.
+struct TuningLoader;
+
+impl AssetLoader for TuningLoader {
+    type Asset = Tuning;
+    type Settings = ();
+    type Error = TuningError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Tuning, TuningError> {
+        let mut content = Vec::new();
+        reader
+            .read_to_end(&mut content)
+            .await
+            .map_err(TuningError::Io)?;
+        Tuning::parse(&content)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+fn watch_tuning_file(mut tuning_file: ResMut<TuningFile>, asset_server: Res<AssetServer>) {
+    tuning_file.handle = asset_server.load(tuning_file.path.clone());
+}
+
+/// Replaces the tuning resources whenever the file changed, the gameplay plugins pick it up from there.
+fn apply_tuning_changes(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<Tuning>>,
+    tunings: Res<Assets<Tuning>>,
+    tuning_file: Res<TuningFile>,
+) {
+    for event in events.read() {
+        if !event.is_modified(&tuning_file.handle) {
+            continue;
+        }
+        let Some(tuning) = tunings.get(&tuning_file.handle) else {
+            continue;
+        };
+
+        commands.insert_resource(tuning.player.clone());
+        commands.insert_resource(tuning.weapon.clone());
+        commands.insert_resource(tuning.asteroids.clone());
+        commands.insert_resource(tuning.waves.clone());
+        commands.insert_resource(tuning.stats.clone());
+        info!("applied changes to tuning file {}", tuning_file.path);
+    }
+}
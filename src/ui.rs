@@ -1,10 +1,19 @@
+use std::collections::HashMap;
+
 use bevy::{
     color::Color,
-    ecs::{component::Component, system::ResMut},
+    ecs::{component::Component, schedule::ScheduleLabel, system::ResMut},
     prelude::*,
 };
 
-use crate::collisions::Stats;
+use rand::RngCore;
+
+use crate::{
+    collisions::Stats,
+    mode::{GameMode, TimeAttackClock},
+    rng::GameRng,
+    tuning::StatsTuning,
+};
 
 const WHITE_COLOR: Color = Color::srgb(1.0, 1.0, 1.0);
 
@@ -14,15 +23,135 @@ pub struct StatsText;
 #[derive(Component)]
 pub struct GameOverText;
 
-#[derive(States, Debug, Clone, PartialEq, Eq, Hash)]
+/// HUD, game over screen and high scores.
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        init_game_state(app);
+
+        app.init_resource::<GameRng>()
+            .init_resource::<StatsTuning>()
+            .init_resource::<GameMode>()
+            .init_resource::<TimeAttackClock>()
+            .init_resource::<HighScores>()
+            .add_systems(Startup, spawn_stats)
+            .add_systems(
+                Update,
+                (
+                    update_stats.run_if(in_state(GameState::InGame)),
+                    handle_game_over_input.run_if(in_state(GameState::GameOver)),
+                ),
+            )
+            .add_systems(OnEnter(GameState::InGame), show_stats)
+            .add_systems(OnEnter(GameState::MainMenu), hide_stats)
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                (record_high_score, spawn_game_over_ui).chain(),
+            )
+            .add_systems(
+                OnExit(GameState::GameOver),
+                (despawn_game_over_ui, next_seed),
+            )
+            .add_systems(RestartRun, restart_rng);
+    }
+}
+
+/// Sets up [`GameState`] and [`RestartRun`] unless another plugin already did.
+pub fn init_game_state(app: &mut App) {
+    if !app.world().contains_resource::<State<GameState>>() {
+        app.init_state::<GameState>()
+            .init_schedule(RestartRun)
+            .add_systems(
+                OnTransition {
+                    exited: GameState::MainMenu,
+                    entered: GameState::InGame,
+                },
+                restart_run,
+            )
+            .add_systems(
+                OnTransition {
+                    exited: GameState::GameOver,
+                    entered: GameState::InGame,
+                },
+                restart_run,
+            );
+    }
+}
+
+#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum GameState {
+    /// Title screen with asteroids drifting in the background.
+    #[default]
+    MainMenu,
     InGame,
+    Paused,
     GameOver,
 }
 
-pub fn spawn_stats(mut commands: Commands, stats: ResMut<Stats>) {
+/// Runs when a new run starts from the main menu, after the previous one ended or was abandoned.
+/// Every plugin resets its entities and resources to how they were at startup here.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RestartRun;
+
+pub fn restart_run(world: &mut World) {
+    world.run_schedule(RestartRun);
+}
+
+/// Starts the first run right away instead of showing the main menu.
+pub fn skip_main_menu(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::InGame);
+}
+
+/// Best score reached in each game mode during this session.
+#[derive(Resource, Default)]
+pub struct HighScores(HashMap<GameMode, u32>);
+
+impl HighScores {
+    pub fn best(&self, mode: GameMode) -> Option<u32> {
+        self.0.get(&mode).copied()
+    }
+
+    /// Remembers `score` if it's the best one in `mode` so far.
+    pub fn record(&mut self, mode: GameMode, score: u32) {
+        let best = self.0.entry(mode).or_default();
+        *best = (*best).max(score);
+    }
+}
+
+/// Health to one decimal, as crashes take off fractions of it.
+/// A ship that's still alive never shows 0.
+pub fn health_text(health: f32) -> String {
+    let shown = if health > 0.0 { health.max(0.1) } else { 0.0 };
+    format!("{shown:.1}")
+}
+
+/// HUD line for the current run, only showing what matters in the game mode.
+fn stats_text(stats: &Stats, mode: GameMode, clock: &TimeAttackClock) -> String {
+    match mode {
+        GameMode::Classic => format!(
+            "Score: {}, Health: {}",
+            stats.score,
+            health_text(stats.health)
+        ),
+        GameMode::Practice => format!("Score: {}", stats.score),
+        GameMode::TimeAttack => format!(
+            "Score: {}, Health: {}, Time: {:.0}",
+            stats.score,
+            health_text(stats.health),
+            clock.0.remaining_secs().ceil()
+        ),
+    }
+}
+
+pub fn spawn_stats(
+    mut commands: Commands,
+    stats: Res<Stats>,
+    mode: Res<GameMode>,
+    clock: Res<TimeAttackClock>,
+) {
     commands.spawn((
-        Text::new(format!("Score: {}, Health: {}", stats.score, stats.health)),
+        Text::new(stats_text(&stats, *mode, &clock)),
         TextFont {
             font_size: 20.0,
             ..Default::default()
@@ -38,21 +167,58 @@ pub fn spawn_stats(mut commands: Commands, stats: ResMut<Stats>) {
             align_items: AlignItems::Center,
             ..default()
         },
+        // only shown once a run started
+        Visibility::Hidden,
         StatsText,
     ));
 }
 
-pub fn update_stats(mut stats_query: Query<&mut Text, With<StatsText>>, stats: Res<Stats>) {
-    if let Ok(mut stats_text) = stats_query.single_mut() {
-        stats_text.0 = format!("Score: {}, Health: {}", stats.score, stats.health);
+fn show_stats(mut query: Query<&mut Visibility, With<StatsText>>) {
+    for mut visibility in query.iter_mut() {
+        *visibility = Visibility::Inherited;
     }
 }
 
-pub fn spawn_game_over_ui(mut commands: Commands, stats: Res<Stats>) {
+fn hide_stats(mut query: Query<&mut Visibility, With<StatsText>>) {
+    for mut visibility in query.iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+pub fn update_stats(
+    mut stats_query: Query<&mut Text, With<StatsText>>,
+    stats: Res<Stats>,
+    mode: Res<GameMode>,
+    clock: Res<TimeAttackClock>,
+) {
+    if let Ok(mut text) = stats_query.single_mut() {
+        text.0 = stats_text(&stats, *mode, &clock);
+    }
+}
+
+fn record_high_score(mut high_scores: ResMut<HighScores>, stats: Res<Stats>, mode: Res<GameMode>) {
+    high_scores.record(*mode, stats.score);
+}
+
+pub fn spawn_game_over_ui(
+    mut commands: Commands,
+    stats: Res<Stats>,
+    rng: Res<GameRng>,
+    mode: Res<GameMode>,
+    high_scores: Res<HighScores>,
+) {
+    let headline = if *mode == GameMode::TimeAttack && stats.health > 0.0 {
+        "Time's up."
+    } else {
+        "You died."
+    };
+
     commands.spawn((
         Text::new(format!(
-            "You died.\nPress Space to restart\nScore: {}",
-            stats.score
+            "{headline}\nPress Space to restart or Escape for the menu\nScore: {}, Best: {}\nSeed: {}",
+            stats.score,
+            high_scores.best(*mode).unwrap_or_default(),
+            rng.seed()
         )),
         TextFont {
             font_size: 50.0,
@@ -73,17 +239,21 @@ pub fn spawn_game_over_ui(mut commands: Commands, stats: Res<Stats>) {
     ));
 }
 
-pub fn despawn_game_over_ui(
-    mut commands: Commands,
-    query: Query<Entity, With<GameOverText>>,
-    mut stats: ResMut<Stats>,
-) {
+pub fn despawn_game_over_ui(mut commands: Commands, query: Query<Entity, With<GameOverText>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn();
     }
+}
+
+fn next_seed(mut rng: ResMut<GameRng>) {
+    // every run gets its own seed, derived from the previous one so a session stays reproducible
+    let seed = rng.next_u64();
+    rng.reseed(seed);
+}
 
-    stats.score = 0;
-    stats.health = 3.0;
+fn restart_rng(mut rng: ResMut<GameRng>) {
+    // the attract screen draws from the rng too, the run shouldn't depend on how long it was shown
+    rng.restart();
 }
 
 pub fn handle_game_over_input(
@@ -92,5 +262,7 @@ pub fn handle_game_over_input(
 ) {
     if keyboard_input.just_pressed(KeyCode::Space) {
         next_state.set(GameState::InGame);
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::MainMenu);
     }
 }
@@ -0,0 +1,319 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::{
+    RES_HEIGHT, RES_WIDTH,
+    asteroids::{Asteroid, spawn_asteroid},
+    kinds::AsteroidKinds,
+    player::Player,
+    rng::GameRng,
+    tuning::{AsteroidTuning, WaveTuning},
+    ui::{GameState, RestartRun, init_game_state},
+    wrap::{ScreenWrap, wrapped_distance},
+};
+
+const WHITE_COLOR: Color = Color::srgb(1.0, 1.0, 1.0);
+
+/// Edge positions tried per step before a spawn waits for the ship to move away.
+const SPAWN_ATTEMPTS: usize = 8;
+
+/// Sends asteroids in waves that get bigger and faster over a run, announcing each one.
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        init_game_state(app);
+
+        app.init_resource::<GameRng>()
+            .init_resource::<AsteroidKinds>()
+            .init_resource::<AsteroidTuning>()
+            .init_resource::<WaveTuning>()
+            .init_resource::<WaveDirector>()
+            .add_systems(Startup, spawn_wave_text)
+            .add_systems(
+                FixedUpdate,
+                direct_waves
+                    .run_if(in_state(GameState::InGame).or(in_state(GameState::MainMenu)))
+                    .before(PhysicsSet::SyncBackend),
+            )
+            .add_systems(Update, announce_wave)
+            .add_systems(RestartRun, reset_waves);
+    }
+}
+
+/// What a single wave consists of.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wave {
+    /// Asteroids from the lower half of the scale range.
+    pub small: u32,
+    /// Asteroids from the upper half of the scale range.
+    pub large: u32,
+    pub spawn_interval_s: f32,
+    /// Factor asteroid speeds are multiplied with.
+    pub speed_multiplier: f32,
+}
+
+impl Wave {
+    /// The wave with the given `number`, starting at 1.
+    pub fn nth(tuning: &WaveTuning, number: u32) -> Self {
+        let waves_before = number.saturating_sub(1);
+        let count = tuning.first_wave + tuning.growth * waves_before;
+        let large_share =
+            (tuning.large_share + tuning.large_share_growth * waves_before as f32).min(1.0);
+        let large = (count as f32 * large_share).round() as u32;
+
+        Self {
+            small: count - large,
+            large,
+            spawn_interval_s: (tuning.spawn_interval_s
+                * tuning.spawn_interval_factor.powi(waves_before as i32))
+            .max(tuning.min_spawn_interval_s),
+            speed_multiplier: tuning
+                .speed_growth
+                .powi(waves_before as i32)
+                .min(tuning.max_speed_multiplier),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WavePhase {
+    /// "Wave N" is shown, nothing spawns yet.
+    Announcing,
+    /// The asteroids of the wave appear one after another.
+    Spawning,
+    /// Everything spawned, waiting for the asteroids to be destroyed.
+    Clearing,
+}
+
+/// Progress of the run through its waves.
+#[derive(Resource)]
+pub struct WaveDirector {
+    /// Number of the current wave, starting at 1.
+    pub wave: u32,
+    pub phase: WavePhase,
+    /// Runs until the phase is over, or until the next asteroid is due while spawning.
+    pub timer: Timer,
+    /// Asteroids of the current wave that are still to be spawned.
+    pub remaining: Wave,
+}
+
+impl FromWorld for WaveDirector {
+    fn from_world(world: &mut World) -> Self {
+        let tuning = world.resource::<WaveTuning>();
+        Self {
+            wave: 1,
+            phase: WavePhase::Announcing,
+            timer: Timer::from_seconds(tuning.break_s, TimerMode::Once),
+            remaining: Wave::nth(tuning, 1),
+        }
+    }
+}
+
+impl WaveDirector {
+    fn announce(&mut self, wave: u32, tuning: &WaveTuning) {
+        self.wave = wave;
+        self.phase = WavePhase::Announcing;
+        self.timer = Timer::from_seconds(tuning.break_s, TimerMode::Once);
+    }
+
+    fn start_spawning(&mut self, tuning: &WaveTuning) {
+        self.remaining = Wave::nth(tuning, self.wave);
+        self.phase = WavePhase::Spawning;
+        self.timer = Timer::from_seconds(self.remaining.spawn_interval_s, TimerMode::Once);
+        // the first asteroid is due right away
+        let interval = self.timer.duration();
+        self.timer.tick(interval);
+    }
+
+    /// Takes one asteroid from the rest of the wave, returns whether it's a large one.
+    fn take_asteroid(&mut self, rng: &mut GameRng) -> Option<bool> {
+        let total = self.remaining.small + self.remaining.large;
+        if total == 0 {
+            return None;
+        }
+
+        let large = rng.random_range(0..total) < self.remaining.large;
+        if large {
+            self.remaining.large -= 1;
+        } else {
+            self.remaining.small -= 1;
+        }
+        Some(large)
+    }
+}
+
+fn reset_waves(mut director: ResMut<WaveDirector>, tuning: Res<WaveTuning>) {
+    director.announce(1, &tuning);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn direct_waves(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    mut director: ResMut<WaveDirector>,
+    asteroids: Query<(), With<Asteroid>>,
+    player: Query<&Transform, With<Player>>,
+    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+    kinds: Res<AsteroidKinds>,
+    asteroid_tuning: Res<AsteroidTuning>,
+    wave_tuning: Res<WaveTuning>,
+    state: Res<State<GameState>>,
+) {
+    director.timer.tick(time.delta());
+
+    let due = match director.phase {
+        WavePhase::Announcing => {
+            let finished = director.timer.finished();
+            if finished {
+                director.start_spawning(&wave_tuning);
+            }
+            finished
+        }
+        // stays due until an asteroid could actually be spawned
+        WavePhase::Spawning => director.timer.finished(),
+        WavePhase::Clearing => {
+            if asteroids.is_empty() || director.timer.finished() {
+                // the attract screen keeps showing the first wave
+                let next = if *state.get() == GameState::MainMenu {
+                    1
+                } else {
+                    director.wave + 1
+                };
+                director.announce(next, &wave_tuning);
+            }
+            false
+        }
+    };
+
+    // the kinds might still be loading
+    if !due
+        || kinds.kinds.is_empty()
+        || asteroids.iter().count() >= asteroid_tuning.max_alive as usize
+    {
+        return;
+    }
+
+    let player = player
+        .single()
+        .ok()
+        .map(|transform| transform.translation.xy());
+    let Some(position) = edge_spawn_position(&mut rng, &kinds, &asteroid_tuning, player) else {
+        return;
+    };
+
+    if let Some(large) = director.take_asteroid(&mut rng)
+        && let Some(kind) = kinds.pick(&mut rng)
+    {
+        let scale = &asteroid_tuning.scale;
+        let middle = (scale.start + scale.end) / 2.0;
+        let scale = if large {
+            rng.random_range(middle..scale.end)
+        } else {
+            rng.random_range(scale.start..middle)
+        };
+
+        // aim somewhere into the middle of the canvas so it crosses the play field
+        let target = Vec2::new(
+            rng.random_range(-(RES_WIDTH as f32 / 4.0)..(RES_WIDTH as f32 / 4.0)),
+            rng.random_range(-(RES_HEIGHT as f32 / 4.0)..(RES_HEIGHT as f32 / 4.0)),
+        );
+        let speed = rng.random_range(asteroid_tuning.speed.clone());
+        let angvel = rng.random_range(asteroid_tuning.angvel.clone());
+
+        let asteroid = spawn_asteroid(
+            &mut commands,
+            &asset_server,
+            &mut images,
+            &mut rng,
+            kind,
+            position.extend(0.0),
+            (target - position).normalize_or_zero() * speed * director.remaining.speed_multiplier,
+            angvel,
+            scale,
+        );
+        commands.entity(asteroid).insert(ScreenWrap::entering());
+    }
+    director.timer.reset();
+
+    if director.remaining.small + director.remaining.large == 0 {
+        director.phase = WavePhase::Clearing;
+        director.timer = Timer::from_seconds(wave_tuning.clear_timeout_s, TimerMode::Once);
+    }
+}
+
+/// A point just outside a random edge of the canvas that's far enough from the ship,
+/// if one was found.
+fn edge_spawn_position(
+    rng: &mut GameRng,
+    kinds: &AsteroidKinds,
+    tuning: &AsteroidTuning,
+    player: Option<Vec2>,
+) -> Option<Vec2> {
+    // far enough out that even the largest asteroid doesn't pop up on screen
+    let margin = kinds.max_radius() * tuning.scale.end;
+    let half_x = RES_WIDTH as f32 / 2.0 + margin;
+    let half_y = RES_HEIGHT as f32 / 2.0 + margin;
+
+    (0..SPAWN_ATTEMPTS)
+        .map(|_| match rng.random_range(0..4) {
+            0 => Vec2::new(rng.random_range(-half_x..half_x), half_y),
+            1 => Vec2::new(rng.random_range(-half_x..half_x), -half_y),
+            2 => Vec2::new(half_x, rng.random_range(-half_y..half_y)),
+            _ => Vec2::new(-half_x, rng.random_range(-half_y..half_y)),
+        })
+        .find(|position| {
+            // the ship might be just across the edge from it
+            player.is_none_or(|player| wrapped_distance(player, *position) >= tuning.safe_radius)
+        })
+}
+
+/// Shows "Wave N" between waves.
+#[derive(Component)]
+struct WaveText;
+
+fn spawn_wave_text(mut commands: Commands) {
+    commands.spawn((
+        Text::default(),
+        TextFont {
+            font_size: 40.0,
+            ..default()
+        },
+        TextColor(WHITE_COLOR),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            top: Val::Percent(40.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        Visibility::Hidden,
+        WaveText,
+    ));
+}
+
+fn announce_wave(
+    director: Res<WaveDirector>,
+    state: Res<State<GameState>>,
+    mut query: Query<(&mut Text, &mut Visibility), With<WaveText>>,
+) {
+    let shown = director.phase == WavePhase::Announcing
+        && matches!(state.get(), GameState::InGame | GameState::Paused);
+
+    for (mut text, mut visibility) in query.iter_mut() {
+        visibility.set_if_neq(if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+
+        let announcement = format!("Wave {}", director.wave);
+        if text.0 != announcement {
+            text.0 = announcement;
+        }
+    }
+}
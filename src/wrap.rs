@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{RES_HEIGHT, RES_WIDTH};
+
+/// Makes the canvas a torus: whatever flies out on one side comes back in on the other.
+pub struct ScreenWrapPlugin;
+
+impl Plugin for ScreenWrapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, wrap_around.before(PhysicsSet::SyncBackend))
+            .add_systems(
+                PostUpdate,
+                (spawn_ghosts, place_ghosts)
+                    .chain()
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// Moves the entity to the opposite edge once it leaves the canvas.
+/// While it straddles an edge, the part that's out is shown on the other side.
+#[derive(Component)]
+pub struct ScreenWrap {
+    /// Whether the entity has been on the canvas yet, only then it starts wrapping.
+    pub on_canvas: bool,
+}
+
+impl ScreenWrap {
+    /// For entities flying in from outside the canvas.
+    pub fn entering() -> Self {
+        Self { on_canvas: false }
+    }
+}
+
+impl Default for ScreenWrap {
+    fn default() -> Self {
+        Self { on_canvas: true }
+    }
+}
+
+/// Offset from `b` to `a` the short way round, which may be across an edge.
+pub fn wrapped_offset(a: Vec2, b: Vec2) -> Vec2 {
+    let canvas = Vec2::new(RES_WIDTH as f32, RES_HEIGHT as f32);
+    let offset = a - b;
+    // a point more than half a canvas away is closer through the edge
+    offset - canvas * (offset / canvas).round()
+}
+
+/// Distance between two points the short way round, which may be across an edge.
+pub fn wrapped_distance(a: Vec2, b: Vec2) -> f32 {
+    wrapped_offset(a, b).length()
+}
+
+/// Copy of a wrapped entity's sprite, one canvas size away along `axes`.
+/// Only ever visible while the entity straddles an edge, the rest of the time it's off the canvas.
+#[derive(Component)]
+struct Ghost {
+    axes: Vec2,
+}
+
+fn wrap_around(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Transform, &mut ScreenWrap)>,
+) {
+    let half = Vec2::new(RES_WIDTH as f32, RES_HEIGHT as f32) / 2.0;
+
+    for (entity, mut transform, mut wrap) in query.iter_mut() {
+        let position = transform.translation.xy();
+
+        if !wrap.on_canvas {
+            if position.abs().cmple(half).all() {
+                wrap.on_canvas = true;
+            } else if position.abs().cmpgt(half * 2.0).any() {
+                // pushed away before it ever made it onto the canvas
+                commands.entity(entity).despawn();
+            }
+            continue;
+        }
+
+        if position.x > half.x {
+            transform.translation.x -= RES_WIDTH as f32;
+        } else if position.x < -half.x {
+            transform.translation.x += RES_WIDTH as f32;
+        }
+        if position.y > half.y {
+            transform.translation.y -= RES_HEIGHT as f32;
+        } else if position.y < -half.y {
+            transform.translation.y += RES_HEIGHT as f32;
+        }
+    }
+}
+
+fn spawn_ghosts(mut commands: Commands, added: Query<(Entity, &Sprite), Added<ScreenWrap>>) {
+    for (entity, sprite) in added.iter() {
+        commands.entity(entity).with_children(|parent| {
+            for axes in [Vec2::X, Vec2::Y, Vec2::ONE] {
+                parent.spawn((sprite.clone(), Transform::default(), Ghost { axes }));
+            }
+        });
+    }
+}
+
+/// Puts the ghosts on the side of the canvas opposite to the entity.
+fn place_ghosts(
+    wrapped: Query<(&Transform, &ScreenWrap, &Sprite, &Children)>,
+    mut ghosts: Query<(&Ghost, &mut Transform, &mut Sprite, &mut Visibility), Without<ScreenWrap>>,
+) {
+    let canvas = Vec2::new(RES_WIDTH as f32, RES_HEIGHT as f32);
+
+    for (transform, wrap, sprite, children) in wrapped.iter() {
+        let position = transform.translation.xy();
+        let side = Vec2::new(-position.x.signum(), -position.y.signum());
+
+        for child in children.iter() {
+            let Ok((ghost, mut ghost_transform, mut ghost_sprite, mut visibility)) =
+                ghosts.get_mut(child)
+            else {
+                continue;
+            };
+
+            // coming in from outside, a ghost would show up in the middle of the other side
+            visibility.set_if_neq(if wrap.on_canvas {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            });
+
+            // ghosts are children, so the offset has to be undone from the entity's rotation and scale
+            let offset = (side * ghost.axes * canvas).extend(0.0);
+            ghost_transform.translation = transform.rotation.inverse() * offset / transform.scale;
+
+            if ghost_sprite.color != sprite.color {
+                ghost_sprite.color = sprite.color;
+            }
+        }
+    }
+}
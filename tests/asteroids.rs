@@ -0,0 +1,358 @@
+mod common;
+
+use std::{path::Path, time::Duration};
+
+use bevy::{log::LogPlugin, prelude::*};
+use bevy_rapier2d::prelude::*;
+use common::{asteroid_kinds, full_game, spawn_kind};
+use spacegame::{
+    HeadlessPlugins, SpaceGamePlugin,
+    asteroids::{Health, split_asteroid},
+    kinds::{
+        AsteroidKinds, AsteroidMaterial, AsteroidShape, KINDS_PATH, KindsError, KindsFilePlugin,
+    },
+    rng::GameRng,
+    rocks::{lumpy_outline, outline_collider, rasterize},
+};
+
+#[test]
+fn spinning_asteroid_keeps_its_momentum_and_spin() {
+    let kinds = asteroid_kinds();
+    let rock = kinds.get("rock").unwrap();
+    let min_scale = rock.split.as_ref().unwrap().min_fragment_scale;
+    let mut rng = GameRng::new(7);
+    let velocity = Velocity {
+        linvel: Vec2::new(-12.0, 30.0),
+        angvel: 1.5,
+    };
+
+    for _ in 0..50 {
+        let fragments = split_asteroid(&mut rng, rock, rock, 0.6, &velocity, 200.0);
+        assert!((2..=4).contains(&fragments.len()));
+
+        let mass: f32 = fragments.iter().map(|f| f.scale * f.scale).sum();
+        assert!((mass - 0.36).abs() < 1e-5);
+        let momentum: Vec2 = fragments.iter().map(|f| f.linvel * f.scale * f.scale).sum();
+        assert!(momentum.abs_diff_eq(velocity.linvel * mass, 1e-3));
+        let center: Vec2 = fragments.iter().map(|f| f.offset * f.scale * f.scale).sum();
+        assert!(center.abs_diff_eq(Vec2::ZERO, 1e-3));
+        assert!(fragments.iter().all(|f| f.scale >= min_scale));
+    }
+}
+
+#[test]
+fn harder_impacts_break_asteroids_into_more_pieces() {
+    let kinds = asteroid_kinds();
+    let rock = kinds.get("rock").unwrap();
+    let mut rng = GameRng::new(7);
+    let velocity = Velocity::linear(Vec2::ZERO);
+
+    let gentle = split_asteroid(&mut rng, rock, rock, 0.6, &velocity, 10.0);
+    let hard = split_asteroid(&mut rng, rock, rock, 0.6, &velocity, 1000.0);
+    assert_eq!(gentle.len(), 2);
+    assert_eq!(hard.len(), 4);
+
+    // too small for four pieces, however hard it's hit
+    let small = split_asteroid(&mut rng, rock, rock, 0.28, &velocity, 1000.0);
+    assert!((2..4).contains(&small.len()));
+    assert!(split_asteroid(&mut rng, rock, rock, 0.2, &velocity, 1000.0).is_empty());
+}
+
+/// Boulders that split into pebbles, a kind with a fifth of the size and a tenth of the mass.
+fn boulders_and_pebbles() -> AsteroidKinds {
+    AsteroidKinds::parse(
+        br#"(
+            kinds: [
+                (
+                    name: "boulder",
+                    sprite: Image(path: "asteroids/1.png", scale: 0.05),
+                    collider: Ball(radius: 40.0),
+                    mass: 1.0,
+                    hit_points: 3.0,
+                    score: (scale: (start: 0.0, end: 1.0), score: (start: 0.0, end: 10.0)),
+                    split: Some((
+                        into: Some("pebble"),
+                        max_fragments: 3,
+                        min_fragment_scale: 0.1,
+                        speed_per_fragment: 100.0,
+                        fragment_spread: 10.0,
+                    )),
+                    spawn_weight: 1.0,
+                ),
+                (
+                    name: "pebble",
+                    sprite: Image(path: "asteroids/1.png", scale: 0.025),
+                    collider: Ball(radius: 10.0),
+                    mass: 0.1,
+                    hit_points: 1.0,
+                    score: (scale: (start: 0.0, end: 1.0), score: (start: 5.0, end: 5.0)),
+                    spawn_weight: 0.0,
+                ),
+            ],
+        )"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn kinds_are_plain_data() {
+    let kinds = boulders_and_pebbles();
+    let boulder = kinds.get("boulder").unwrap();
+    assert_eq!(boulder.score.at(0.55), 5);
+    assert_eq!(kinds.fragments_of(boulder).name, "pebble");
+    assert_eq!(kinds.max_radius(), 40.0);
+
+    // pebbles are only ever fragments
+    let mut rng = GameRng::new(7);
+    for _ in 0..20 {
+        assert_eq!(kinds.pick(&mut rng).unwrap().name, "boulder");
+    }
+}
+
+#[test]
+fn fragments_of_another_kind_keep_the_mass_and_momentum() {
+    let kinds = boulders_and_pebbles();
+    let boulder = kinds.get("boulder").unwrap();
+    let pebble = kinds.fragments_of(boulder);
+    let mut rng = GameRng::new(7);
+    let velocity = Velocity {
+        linvel: Vec2::new(20.0, -5.0),
+        angvel: -0.8,
+    };
+
+    for _ in 0..50 {
+        let fragments = split_asteroid(&mut rng, boulder, pebble, 0.5, &velocity, 150.0);
+        assert!((2..=3).contains(&fragments.len()));
+
+        let mass: f32 = fragments
+            .iter()
+            .map(|f| pebble.mass * f.scale * f.scale)
+            .sum();
+        assert!((mass - boulder.mass * 0.25).abs() < 1e-5);
+        let momentum: Vec2 = fragments
+            .iter()
+            .map(|f| f.linvel * pebble.mass * f.scale * f.scale)
+            .sum();
+        assert!((momentum - velocity.linvel * mass).length() < 1e-3);
+        for fragment in &fragments {
+            assert!(fragment.offset.length() <= boulder.collider.radius() * 0.5);
+        }
+    }
+}
+
+#[test]
+fn fragments_must_be_a_known_kind() {
+    let error = AsteroidKinds::parse(
+        br#"(
+            kinds: [
+                (
+                    name: "rock",
+                    sprite: Image(path: "asteroids/1.png", scale: 0.025),
+                    collider: Ball(radius: 25.0),
+                    mass: 0.2,
+                    hit_points: 1.0,
+                    score: (scale: (start: 0.3, end: 0.6), score: (start: 4.0, end: 2.0)),
+                    split: Some((
+                        into: Some("gravel"),
+                        max_fragments: 4,
+                        min_fragment_scale: 0.15,
+                        speed_per_fragment: 120.0,
+                        fragment_spread: 15.0,
+                    )),
+                    spawn_weight: 1.0,
+                ),
+            ],
+        )"#,
+    )
+    .unwrap_err();
+
+    assert!(matches!(
+        error,
+        KindsError::Invalid { ref field, .. } if field == "rock.split.into"
+    ));
+}
+
+#[test]
+fn every_rock_is_generated_with_a_matching_sprite() {
+    let kinds = asteroid_kinds();
+    let rock = kinds.get("rock").unwrap();
+    let mut rng = GameRng::new(7);
+
+    let first = lumpy_outline(&mut rng, 10.0, 0.35, 9..14);
+    let second = lumpy_outline(&mut rng, 10.0, 0.35, 9..14);
+    assert_ne!(first, second);
+    for corner in &first {
+        assert!((6.5 - 1e-4..=10.0 + 1e-4).contains(&corner.length()));
+    }
+
+    // lumps go inwards, the sprite is just big enough for the full radius
+    let collider = outline_collider(&first);
+    let image = rasterize(&collider, 10.0, 0.5);
+    assert_eq!(image.size(), UVec2::new(22, 22));
+    assert_eq!(image.get_color_at(11, 11).unwrap().alpha(), 1.0);
+    assert_eq!(image.get_color_at(0, 0).unwrap().alpha(), 0.0);
+    assert_eq!(image.get_color_at(21, 21).unwrap().alpha(), 0.0);
+    for corner in &first {
+        assert!(collider.contains_point(Vec2::ZERO, 0.0, *corner * 0.99));
+        assert!(!collider.contains_point(Vec2::ZERO, 0.0, *corner * 1.01));
+    }
+
+    assert!(rock.collider.is_generated());
+    assert_eq!(rock.collider.radius(), 25.0);
+}
+
+#[test]
+fn compound_colliders_keep_their_dents() {
+    let mut rng = GameRng::new(7);
+    let kinds = AsteroidKinds::parse(
+        br#"(
+            kinds: [
+                (
+                    name: "horseshoe",
+                    sprite: Image(path: "asteroids/1.png", scale: 0.025),
+                    collider: Compound(parts: [
+                        [(-20.0, -20.0), (-10.0, -20.0), (-10.0, 20.0), (-20.0, 20.0)],
+                        [(10.0, -20.0), (20.0, -20.0), (20.0, 20.0), (10.0, 20.0)],
+                        [(-20.0, -20.0), (20.0, -20.0), (20.0, -10.0), (-20.0, -10.0)],
+                    ]),
+                    mass: 0.2,
+                    hit_points: 1.0,
+                    score: (scale: (start: 0.3, end: 0.6), score: (start: 4.0, end: 2.0)),
+                    spawn_weight: 1.0,
+                ),
+            ],
+        )"#,
+    )
+    .unwrap();
+    let horseshoe = kinds
+        .get("horseshoe")
+        .unwrap()
+        .collider
+        .collider(2.0, &mut rng);
+    assert!(horseshoe.contains_point(Vec2::ZERO, 0.0, Vec2::new(-30.0, 30.0)));
+    // the dent in the middle
+    assert!(!horseshoe.contains_point(Vec2::ZERO, 0.0, Vec2::new(0.0, 10.0)));
+}
+
+#[test]
+fn infinite_values_are_rejected() {
+    let invalid_field = |kinds: &AsteroidKinds| match kinds.validate() {
+        Err(KindsError::Invalid { field, .. }) => Some(field),
+        _ => None,
+    };
+
+    let mut kinds = boulders_and_pebbles();
+    kinds.kinds[0].spawn_weight = f32::INFINITY;
+    assert_eq!(
+        invalid_field(&kinds).as_deref(),
+        Some("boulder.spawn_weight")
+    );
+
+    let mut kinds = boulders_and_pebbles();
+    kinds.kinds[1].collider = AsteroidShape::Lumpy {
+        radius: f32::INFINITY,
+        lumpiness: 0.3,
+        corners: 5..8,
+    };
+    assert_eq!(invalid_field(&kinds).as_deref(), Some("pebble.collider"));
+
+    let mut kinds = boulders_and_pebbles();
+    kinds.kinds[1].material = AsteroidMaterial::Crystal { heal: f32::NAN };
+    assert_eq!(invalid_field(&kinds).as_deref(), Some("pebble.material"));
+
+    let mut kinds = boulders_and_pebbles();
+    kinds.kinds[0].spawn_weight = f32::MAX;
+    kinds.kinds[1].spawn_weight = f32::MAX;
+    assert_eq!(invalid_field(&kinds).as_deref(), Some("spawn_weight"));
+}
+
+#[test]
+fn flat_polygons_are_rejected() {
+    let error = AsteroidKinds::parse(
+        br#"(
+            kinds: [
+                (
+                    name: "needle",
+                    sprite: Image(path: "asteroids/1.png", scale: 0.025),
+                    collider: Polygon(points: [(-10.0, 0.0), (0.0, 0.0), (10.0, 0.0)]),
+                    mass: 0.2,
+                    hit_points: 1.0,
+                    score: (scale: (start: 0.3, end: 0.6), score: (start: 4.0, end: 2.0)),
+                    spawn_weight: 1.0,
+                ),
+            ],
+        )"#,
+    )
+    .unwrap_err();
+
+    assert!(matches!(
+        error,
+        KindsError::Invalid { ref field, .. } if field == "needle.collider"
+    ));
+}
+
+#[test]
+fn changed_hit_points_apply_to_live_asteroids() {
+    let mut app = full_game();
+    let asteroid = spawn_kind(&mut app, "rock", Vec2::ZERO, Vec2::ZERO, 0.5);
+    app.update();
+    app.world_mut().get_mut::<Health>(asteroid).unwrap().current /= 2.0;
+
+    let mut kinds = app.world_mut().resource_mut::<AsteroidKinds>();
+    let hit_points = &mut kinds
+        .kinds
+        .iter_mut()
+        .find(|kind| kind.name == "rock")
+        .unwrap()
+        .hit_points;
+    *hit_points *= 2.0;
+    let max = *hit_points * 0.5;
+    app.update();
+
+    let health = app.world().get::<Health>(asteroid).unwrap();
+    assert!((health.max - max).abs() < 1e-5);
+    assert!((health.current - max / 2.0).abs() < 1e-5);
+}
+
+#[test]
+fn a_kinds_file_given_at_launch_is_watched_for_changes() {
+    let dir = std::env::temp_dir().join(format!("spacegame-kinds-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("test.kinds.ron");
+    let shipped = std::fs::read_to_string(Path::new("assets").join(KINDS_PATH)).unwrap();
+    std::fs::write(&path, &shipped).unwrap();
+
+    let mut app = App::new();
+    app.add_plugins(KindsFilePlugin { path: path.clone() })
+        .add_plugins(HeadlessPlugins.build().disable::<LogPlugin>())
+        .add_plugins(SpaceGamePlugin {
+            headless: true,
+            skip_menu: true,
+        });
+    let rock_hit_points = |app: &App| {
+        app.world()
+            .resource::<AsteroidKinds>()
+            .get("rock")
+            .map(|rock| rock.hit_points)
+    };
+    let wait_for = |app: &mut App, hit_points: f32| {
+        for _ in 0..500 {
+            app.update();
+            if rock_hit_points(app) == Some(hit_points) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        false
+    };
+
+    assert!(wait_for(&mut app, 3.0));
+    std::fs::write(
+        &path,
+        shipped.replacen("hit_points: 3.0", "hit_points: 7.0", 1),
+    )
+    .unwrap();
+    let reloaded = wait_for(&mut app, 7.0);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(reloaded);
+}
@@ -0,0 +1,363 @@
+mod common;
+
+use bevy::{math::Vec2, prelude::*};
+use bevy_rapier2d::{prelude::*, rapier::geometry::CollisionEventFlags};
+use common::{TestGame, asteroid_kinds};
+use spacegame::{
+    FIXED_HZ,
+    asteroids::{Asteroid, Flash, Health},
+    mode::GameMode,
+    player::Invulnerable,
+    shooting::Projectile,
+    tuning::PlayerTuning,
+    ui::GameState,
+};
+
+/// Impulse of a crash that costs the ship one health.
+fn one_health(game: &TestGame) -> f32 {
+    1.0 / game.app.world().resource::<PlayerTuning>().impact_damage
+}
+
+fn assert_health(game: &TestGame, health: f32) {
+    assert!(
+        (game.stats().health - health).abs() < 1e-4,
+        "health is {}, not {health}",
+        game.stats().health
+    );
+}
+
+#[test]
+fn projectile_splits_large_asteroid() {
+    let mut game = TestGame::new();
+    let asteroid = game.spawn_asteroid(0.6, 2);
+    let projectile = game.spawn_projectile();
+
+    game.collide(projectile, asteroid);
+
+    assert_eq!(game.stats().score, 2);
+    assert!(!game.exists(asteroid));
+    assert!(!game.exists(projectile));
+
+    let fragments: Vec<(f32, Vec2, Vec2, f32)> = game
+        .app
+        .world_mut()
+        .query::<(&Asteroid, &Transform, &Velocity)>()
+        .iter(game.app.world())
+        .map(|(fragment, transform, velocity)| {
+            (
+                fragment.scale,
+                transform.translation.xy(),
+                velocity.linvel,
+                velocity.angvel,
+            )
+        })
+        .collect();
+    assert!((2..=4).contains(&fragments.len()));
+
+    // mass goes with the area, and the pieces together carry on like the asteroid did
+    let mass: f32 = fragments.iter().map(|(scale, ..)| scale * scale).sum();
+    assert!((mass - 0.36).abs() < 1e-5);
+    let momentum: Vec2 = fragments
+        .iter()
+        .map(|(scale, _, linvel, _)| *linvel * scale * scale)
+        .sum();
+    assert!(momentum.abs_diff_eq(Vec2::new(5.0, 0.0) * 0.36, 1e-4));
+    let center: Vec2 = fragments
+        .iter()
+        .map(|(scale, position, ..)| *position * scale * scale)
+        .sum::<Vec2>()
+        / mass;
+    assert!(center.abs_diff_eq(Vec2::new(10.0, 20.0), 1e-3));
+    for (_, _, _, angvel) in fragments {
+        assert_eq!(angvel, 0.0);
+    }
+}
+
+#[test]
+fn small_asteroid_is_destroyed_without_fragments() {
+    let mut game = TestGame::new();
+    let asteroid = game.spawn_asteroid(0.2, 3);
+    let projectile = game.spawn_projectile();
+
+    game.collide(projectile, asteroid);
+
+    assert_eq!(game.stats().score, 3);
+    assert_eq!(game.count::<Asteroid>(), 0);
+    assert_eq!(game.count::<Projectile>(), 0);
+}
+
+#[test]
+fn asteroids_without_health_are_left_alone() {
+    let mut game = TestGame::new();
+    let asteroid = game.spawn_asteroid(0.2, 3);
+    game.app.world_mut().entity_mut(asteroid).remove::<Health>();
+    let projectile = game.spawn_projectile();
+
+    game.collide(projectile, asteroid);
+
+    assert!(game.exists(asteroid));
+    assert_eq!(game.stats().score, 0);
+}
+
+#[test]
+fn large_asteroids_take_several_hits() {
+    let mut game = TestGame::new();
+    let asteroid = game.spawn_asteroid_of("rock", 0.6, Vec2::ZERO);
+
+    let projectile = game.spawn_projectile();
+    game.collide(projectile, asteroid);
+    assert!(game.exists(asteroid));
+    assert!(!game.exists(projectile));
+    assert!(game.app.world().entity(asteroid).contains::<Flash>());
+    assert_eq!(game.stats().score, 0);
+
+    let health = game.app.world().get::<Health>(asteroid).unwrap();
+    assert!(health.current > 0.0 && health.current < health.max);
+
+    let projectile = game.spawn_projectile();
+    game.collide(projectile, asteroid);
+    assert!(!game.exists(asteroid));
+    assert_eq!(game.stats().score, 1);
+}
+
+#[test]
+fn stronger_projectiles_break_asteroids_at_once() {
+    let mut game = TestGame::new();
+    let asteroid = game.spawn_asteroid_of("rock", 0.6, Vec2::ZERO);
+    let projectile = game.spawn_projectile();
+    game.app
+        .world_mut()
+        .get_mut::<Projectile>(projectile)
+        .unwrap()
+        .damage = 2.0;
+
+    game.collide(projectile, asteroid);
+
+    assert!(!game.exists(asteroid));
+    assert_eq!(game.stats().score, 1);
+}
+
+#[test]
+fn entity_order_in_event_does_not_matter() {
+    let mut game = TestGame::new();
+    let asteroid = game.spawn_asteroid(0.2, 3);
+    let projectile = game.spawn_projectile();
+
+    game.collide(asteroid, projectile);
+
+    assert_eq!(game.stats().score, 3);
+    assert_eq!(game.count::<Asteroid>(), 0);
+    assert_eq!(game.count::<Projectile>(), 0);
+}
+
+#[test]
+fn player_loses_health_on_asteroid_hit() {
+    let mut game = TestGame::new();
+    let player = game.spawn_player();
+    let asteroid = game.spawn_asteroid(0.4, 3);
+
+    game.crash(player, asteroid, one_health(&game));
+
+    assert_health(&game, 2.0);
+    assert_eq!(game.stats().score, 0);
+    assert!(game.exists(asteroid));
+    assert_eq!(game.state(), GameState::InGame);
+}
+
+#[test]
+fn last_hit_ends_the_run() {
+    let mut game = TestGame::new();
+    game.stats_mut().health = 1.0;
+    let player = game.spawn_player();
+    let asteroid = game.spawn_asteroid(0.4, 3);
+
+    game.crash(asteroid, player, 1.5 * one_health(&game));
+    game.update();
+
+    assert!(game.stats().health <= 0.0);
+    assert_eq!(game.state(), GameState::GameOver);
+}
+
+#[test]
+fn no_collisions_are_handled_after_game_over() {
+    let mut game = TestGame::new();
+    game.stats_mut().health = 1.0;
+    let player = game.spawn_player();
+    let asteroid = game.spawn_asteroid(0.4, 3);
+    let projectile = game.spawn_projectile();
+
+    game.crash(player, asteroid, 2.0 * one_health(&game));
+    game.update();
+    game.collide(projectile, asteroid);
+
+    assert_eq!(game.stats().score, 0);
+    assert!(game.exists(asteroid));
+}
+
+#[test]
+fn hits_while_invulnerable_do_not_hurt() {
+    let mut game = TestGame::new();
+    let player = game.spawn_player();
+    let asteroid1 = game.spawn_asteroid(0.4, 3);
+    let asteroid2 = game.spawn_asteroid(0.5, 2);
+
+    let hit = one_health(&game);
+    game.crash(player, asteroid1, hit);
+    game.crash(player, asteroid1, hit);
+    game.crash(asteroid2, player, hit);
+
+    assert_health(&game, 2.0);
+    assert!(game.app.world().get::<Invulnerable>(player).is_some());
+}
+
+#[test]
+fn touching_several_asteroids_at_once_is_one_hit_by_the_hardest() {
+    let mut game = TestGame::new();
+    let player = game.spawn_player();
+    let asteroid1 = game.spawn_asteroid(0.4, 3);
+    let asteroid2 = game.spawn_asteroid(0.5, 2);
+
+    for (asteroid, damage) in [(asteroid1, 0.5), (asteroid2, 1.5)] {
+        let force = damage * one_health(&game) * FIXED_HZ as f32;
+        game.app.world_mut().send_event(ContactForceEvent {
+            collider1: player,
+            collider2: asteroid,
+            total_force: Vect::X * force,
+            total_force_magnitude: force,
+            max_force_direction: Vect::X,
+            max_force_magnitude: force,
+        });
+    }
+    game.update();
+
+    assert_health(&game, 1.5);
+}
+
+#[test]
+fn harder_crashes_hurt_more() {
+    let mut game = TestGame::new();
+    let player = game.spawn_player();
+    let asteroid = game.spawn_asteroid(0.4, 3);
+
+    game.crash(player, asteroid, 0.1 * one_health(&game));
+    assert_health(&game, 2.9);
+
+    game.app
+        .world_mut()
+        .entity_mut(player)
+        .remove::<Invulnerable>();
+    game.crash(player, asteroid, 2.0 * one_health(&game));
+    assert_health(&game, 0.9);
+}
+
+#[test]
+fn touching_alone_does_not_hurt() {
+    let mut game = TestGame::new();
+    let player = game.spawn_player();
+    let asteroid = game.spawn_asteroid(0.4, 3);
+
+    game.collide(player, asteroid);
+
+    assert_eq!(game.stats().health, 3.0);
+    assert!(game.app.world().get::<Invulnerable>(player).is_none());
+}
+
+#[test]
+fn player_can_be_hurt_again_after_invulnerability() {
+    let mut game = TestGame::new();
+    let player = game.spawn_player();
+    let asteroid = game.spawn_asteroid(0.4, 3);
+
+    game.crash(player, asteroid, one_health(&game));
+    game.app
+        .world_mut()
+        .entity_mut(player)
+        .remove::<Invulnerable>();
+    game.crash(player, asteroid, one_health(&game));
+
+    assert_health(&game, 1.0);
+}
+
+#[test]
+fn hit_knocks_player_away_from_asteroid() {
+    let mut game = TestGame::new();
+    let player = game.spawn_player();
+    // flying straight into the asteroid
+    game.app
+        .world_mut()
+        .entity_mut(player)
+        .insert(Velocity::linear(Vec2::new(10.0, 20.0)));
+    let asteroid = game.spawn_asteroid(0.4, 3);
+
+    game.crash(player, asteroid, one_health(&game));
+
+    let velocity = game.app.world().get::<Velocity>(player).unwrap();
+    let away = -Vec2::new(10.0, 20.0).normalize();
+    let speed = PlayerTuning::default().knockback_speed;
+    assert!((velocity.linvel.dot(away) - speed).abs() < 1e-3);
+}
+
+#[test]
+fn practice_mode_does_not_hurt() {
+    let mut game = TestGame::with_mode(GameMode::Practice);
+    let player = game.spawn_player();
+    let asteroid = game.spawn_asteroid(0.4, 3);
+
+    for _ in 0..5 {
+        game.crash(player, asteroid, 5.0 * one_health(&game));
+    }
+    game.update();
+
+    assert_eq!(game.stats().health, 3.0);
+    assert_eq!(game.state(), GameState::InGame);
+}
+
+#[test]
+fn asteroids_bumping_into_each_other_change_nothing() {
+    let mut game = TestGame::new();
+    let asteroid1 = game.spawn_asteroid(0.4, 3);
+    let asteroid2 = game.spawn_asteroid(0.5, 2);
+
+    game.collide(asteroid1, asteroid2);
+
+    assert_eq!(game.stats().score, 0);
+    assert_eq!(game.stats().health, 3.0);
+    assert_eq!(game.count::<Asteroid>(), 2);
+}
+
+#[test]
+fn stopped_collisions_are_ignored() {
+    let mut game = TestGame::new();
+    let player = game.spawn_player();
+    let asteroid = game.spawn_asteroid(0.4, 3);
+
+    game.app.world_mut().send_event(CollisionEvent::Stopped(
+        player,
+        asteroid,
+        CollisionEventFlags::empty(),
+    ));
+    game.update();
+
+    assert_eq!(game.stats().health, 3.0);
+}
+
+#[test]
+fn fragment_colliders_are_sized_in_canvas_pixels() {
+    let mut game = TestGame::new();
+    let asteroid = game.spawn_asteroid(0.6, 2);
+    let projectile = game.spawn_projectile();
+
+    game.collide(projectile, asteroid);
+
+    let kinds = asteroid_kinds();
+    let radius = kinds.get("rock").unwrap().collider.radius();
+    let mut fragments = game.app.world_mut().query::<(&Asteroid, &Collider)>();
+    for (fragment, collider) in fragments.iter(game.app.world()) {
+        let reach = radius * fragment.scale;
+        // generated lumps only ever go inwards
+        let aabb = collider.raw.compute_local_aabb();
+        assert!(aabb.maxs.x <= reach + 1e-4 && aabb.mins.x >= -reach - 1e-4);
+        assert!(aabb.maxs.y <= reach + 1e-4 && aabb.mins.y >= -reach - 1e-4);
+        assert!(aabb.maxs.x >= reach * 0.5);
+    }
+}
@@ -0,0 +1,350 @@
+//! Headless test harness for gameplay rules.
+
+#![allow(dead_code)]
+
+use std::{path::Path, time::Duration};
+
+use bevy::{
+    ecs::system::RunSystemOnce,
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    log::LogPlugin,
+    prelude::*,
+    state::app::StatesPlugin,
+    time::TimeUpdateStrategy,
+};
+use bevy_rapier2d::{prelude::*, rapier::geometry::CollisionEventFlags};
+use spacegame::{
+    CollisionPlugin, FIXED_HZ, HeadlessPlugins, SpaceGamePlugin,
+    asteroids::{Asteroid, Health, spawn_asteroid},
+    collisions::Stats,
+    interpolation::Interpolated,
+    kinds::{AsteroidKinds, KINDS_PATH},
+    mode::GameMode,
+    player::Player,
+    rng::GameRng,
+    shooting::Projectile,
+    tuning::WeaponTuning,
+    ui::GameState,
+};
+
+/// The asteroid kinds the game ships with.
+pub fn asteroid_kinds() -> AsteroidKinds {
+    AsteroidKinds::load(&Path::new("assets").join(KINDS_PATH)).unwrap()
+}
+
+/// The whole game including physics, as it runs with `--headless`, already in the first run.
+/// Time stands still until a `TimeUpdateStrategy` with a duration is inserted.
+pub fn full_game() -> App {
+    launch(true)
+}
+
+/// Like [`full_game`], but showing the main menu.
+pub fn main_menu() -> App {
+    launch(false)
+}
+
+/// Like [`main_menu`], with `plugins` such as recording or replaying added, and not updated yet.
+pub fn main_menu_with<M>(plugins: impl bevy::app::Plugins<M>) -> App {
+    let mut app = headless_game(false);
+    app.add_plugins(plugins);
+    app
+}
+
+/// The asteroid kinds are there from the first frame instead of loading in the background,
+/// so every run lines up frame by frame and waves can spawn right away.
+fn headless_game(skip_menu: bool) -> App {
+    let mut app = App::new();
+    app.add_plugins(HeadlessPlugins.build().disable::<LogPlugin>())
+        .insert_resource(GameRng::new(0))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO))
+        .insert_resource(asteroid_kinds())
+        .add_plugins(SpaceGamePlugin {
+            headless: true,
+            skip_menu,
+        });
+    app
+}
+
+fn launch(skip_menu: bool) -> App {
+    let mut app = headless_game(skip_menu);
+    app.update();
+    app
+}
+
+/// Number of entities with the component `C`.
+pub fn count<C: Component>(app: &mut App) -> usize {
+    app.world_mut()
+        .query_filtered::<(), With<C>>()
+        .iter(app.world())
+        .count()
+}
+
+/// Taps a key for a single frame, going through input events so it also counts as just pressed.
+pub fn tap(app: &mut App, key_code: KeyCode, logical_key: Key) {
+    for state in [ButtonState::Pressed, ButtonState::Released] {
+        app.world_mut().send_event(KeyboardInput {
+            key_code,
+            logical_key: logical_key.clone(),
+            state,
+            text: None,
+            repeat: false,
+            window: Entity::PLACEHOLDER,
+        });
+        app.update();
+    }
+}
+
+/// Like [`full_game`] in the given `mode`, with every frame running exactly one fixed step.
+pub fn fixed_step_game(mode: GameMode) -> App {
+    let mut app = full_game();
+    app.insert_resource(mode);
+    step_by_step(&mut app);
+    app
+}
+
+/// Makes every following frame run exactly one fixed step.
+pub fn step_by_step(app: &mut App) {
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(
+        Time::<Fixed>::from_hz(FIXED_HZ).timestep(),
+    ));
+}
+
+pub fn player(app: &mut App) -> Entity {
+    app.world_mut()
+        .query_filtered::<Entity, With<Player>>()
+        .single(app.world())
+        .unwrap()
+}
+
+/// Moves the ship to `transform` at once, without interpolating from where it was.
+pub fn place_player(app: &mut App, transform: Transform) -> Entity {
+    let player = player(app);
+    app.world_mut()
+        .entity_mut(player)
+        .insert((transform, Interpolated::from(transform)));
+    player
+}
+
+/// Spawns an asteroid of one of the loaded kinds into the running game, the way waves do.
+pub fn spawn_kind(app: &mut App, kind: &str, position: Vec2, linvel: Vec2, scale: f32) -> Entity {
+    let kind = kind.to_owned();
+    app.world_mut()
+        .run_system_once(
+            move |mut commands: Commands,
+                  asset_server: Res<AssetServer>,
+                  mut images: ResMut<Assets<Image>>,
+                  mut rng: ResMut<GameRng>,
+                  kinds: Res<AsteroidKinds>| {
+                spawn_asteroid(
+                    &mut commands,
+                    &asset_server,
+                    &mut images,
+                    &mut rng,
+                    kinds.get(&kind).unwrap(),
+                    position.extend(0.0),
+                    linvel,
+                    0.0,
+                    scale,
+                )
+            },
+        )
+        .unwrap()
+}
+
+/// Flies forward and shoots for a few seconds, one fixed step per frame.
+pub fn play_for_a_while(app: &mut App) {
+    step_by_step(app);
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::ArrowUp);
+    for _ in 0..10 {
+        for _ in 0..20 {
+            app.update();
+        }
+        tap(app, KeyCode::Space, Key::Space);
+    }
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .release(KeyCode::ArrowUp);
+}
+
+pub fn game_state(app: &App) -> GameState {
+    app.world().resource::<State<GameState>>().get().clone()
+}
+
+/// A game without window, renderer or physics engine.
+/// Collisions don't happen on their own, they're injected with [`TestGame::collide`]
+/// and [`TestGame::crash`].
+/// Every update runs exactly one fixed step.
+pub struct TestGame {
+    pub app: App,
+}
+
+impl TestGame {
+    pub fn new() -> Self {
+        Self::with_mode(GameMode::Classic)
+    }
+
+    pub fn with_mode(mode: GameMode) -> Self {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            ImagePlugin::default(),
+            StatesPlugin,
+        ))
+        .insert_resource(Time::<Fixed>::from_hz(FIXED_HZ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / FIXED_HZ,
+        )))
+        .add_event::<CollisionEvent>()
+        .add_event::<ContactForceEvent>()
+        .insert_resource(GameRng::new(0))
+        .insert_resource(asteroid_kinds())
+        .insert_resource(mode)
+        .add_plugins(CollisionPlugin);
+
+        // run startup and start a run
+        app.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::InGame);
+        app.update();
+
+        Self { app }
+    }
+
+    pub fn spawn_player(&mut self) -> Entity {
+        self.app
+            .world_mut()
+            .spawn((Player, Transform::default(), Velocity::default()))
+            .id()
+    }
+
+    /// A rock that breaks on the first hit of the default weapon.
+    pub fn spawn_asteroid(&mut self, scale: f32, score: u32) -> Entity {
+        self.app
+            .world_mut()
+            .spawn((
+                Asteroid {
+                    kind: "rock".into(),
+                    score,
+                    scale,
+                },
+                Health::new(WeaponTuning::default().damage),
+                Transform::from_xyz(10.0, 20.0, 0.0),
+                Velocity::linear(Vec2::new(5.0, 0.0)),
+            ))
+            .id()
+    }
+
+    /// An asteroid of one of the shipped kinds, worth a single point.
+    pub fn spawn_asteroid_of(&mut self, kind: &str, scale: f32, position: Vec2) -> Entity {
+        let kinds = asteroid_kinds();
+        let kind_of = kinds.get(kind).unwrap();
+        self.app
+            .world_mut()
+            .spawn((
+                Asteroid {
+                    kind: kind.into(),
+                    score: 1,
+                    scale,
+                },
+                kind_of.material,
+                Health::new(kind_of.hit_points * scale),
+                Transform::from_translation(position.extend(0.0)),
+                Velocity::default(),
+            ))
+            .id()
+    }
+
+    pub fn spawn_projectile(&mut self) -> Entity {
+        let velocity = Vec2::new(0.0, 200.0);
+        self.app
+            .world_mut()
+            .spawn((
+                Projectile::new(240.0, 1.0),
+                Transform::default(),
+                Velocity::linear(velocity),
+            ))
+            .id()
+    }
+
+    /// Lets two entities start touching and runs a frame.
+    pub fn collide(&mut self, entity1: Entity, entity2: Entity) {
+        self.app.world_mut().send_event(CollisionEvent::Started(
+            entity1,
+            entity2,
+            CollisionEventFlags::empty(),
+        ));
+        self.app.update();
+    }
+
+    /// Lets two entities push each other apart with `impulse` over one step and runs a frame.
+    /// The impulse is mass times canvas pixels per second.
+    pub fn crash(&mut self, entity1: Entity, entity2: Entity, impulse: f32) {
+        let force = impulse * FIXED_HZ as f32;
+        self.app.world_mut().send_event(ContactForceEvent {
+            collider1: entity1,
+            collider2: entity2,
+            total_force: Vect::X * force,
+            total_force_magnitude: force,
+            max_force_direction: Vect::X,
+            max_force_magnitude: force,
+        });
+        self.app.update();
+    }
+
+    pub fn update(&mut self) {
+        self.app.update();
+    }
+
+    pub fn stats(&self) -> &Stats {
+        self.app.world().resource::<Stats>()
+    }
+
+    pub fn stats_mut(&mut self) -> Mut<'_, Stats> {
+        self.app.world_mut().resource_mut::<Stats>()
+    }
+
+    pub fn state(&self) -> GameState {
+        self.app
+            .world()
+            .resource::<State<GameState>>()
+            .get()
+            .clone()
+    }
+
+    pub fn exists(&self, entity: Entity) -> bool {
+        self.app.world().get_entity(entity).is_ok()
+    }
+
+    pub fn count<C: Component>(&mut self) -> usize {
+        self.app
+            .world_mut()
+            .query_filtered::<(), With<C>>()
+            .iter(self.app.world())
+            .count()
+    }
+
+    pub fn asteroids(&mut self) -> Vec<(Asteroid, Vec2)> {
+        self.app
+            .world_mut()
+            .query::<(&Asteroid, &Transform)>()
+            .iter(self.app.world())
+            .map(|(asteroid, transform)| {
+                (
+                    Asteroid {
+                        kind: asteroid.kind.clone(),
+                        score: asteroid.score,
+                        scale: asteroid.scale,
+                    },
+                    transform.translation.xy(),
+                )
+            })
+            .collect()
+    }
+}
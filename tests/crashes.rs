@@ -0,0 +1,62 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use common::{TestGame, fixed_step_game, spawn_kind};
+use spacegame::{
+    FIXED_HZ, collisions::Stats, mode::GameMode, player::Invulnerable, tuning::PlayerTuning,
+};
+
+/// Health the ship loses when a `kind` asteroid of `scale` flies at it from above with `speed`,
+/// `miss` pixels off center, with real physics.
+fn crash(kind: &'static str, scale: f32, speed: f32, miss: f32) -> f32 {
+    let mut app = fixed_step_game(GameMode::Classic);
+    let health = app.world().resource::<Stats>().health;
+
+    spawn_kind(
+        &mut app,
+        kind,
+        Vec2::new(miss, 40.0),
+        Vec2::new(0.0, -speed),
+        scale,
+    );
+
+    // long enough for the asteroid to hit or fly past
+    for _ in 0..FIXED_HZ as u32 {
+        app.update();
+    }
+    health - app.world().resource::<Stats>().health
+}
+
+#[test]
+fn grazing_a_pebble_barely_hurts() {
+    let damage = crash("rock", 0.15, 40.0, 11.0);
+    assert!(damage > 0.0 && damage < 0.2, "took {damage}");
+}
+
+#[test]
+fn ramming_a_large_rock_is_deadly() {
+    let damage = crash("rock", 0.6, 300.0, 0.0);
+    assert!(damage >= 3.0, "took {damage}");
+}
+
+#[test]
+fn a_graze_does_not_shield_from_the_crash_right_after() {
+    let mut game = TestGame::new();
+    let player = game.spawn_player();
+    let pebble = game.spawn_asteroid(0.15, 1);
+    let rock = game.spawn_asteroid(0.6, 1);
+    let impact_damage = game.app.world().resource::<PlayerTuning>().impact_damage;
+    let knockback_speed = game.app.world().resource::<PlayerTuning>().knockback_speed;
+
+    game.crash(player, pebble, 0.05 / impact_damage);
+    let health = game.stats().health;
+    assert!(health < 3.0 && health > 2.9);
+    assert!(game.app.world().get::<Invulnerable>(player).is_none());
+    let velocity = game.app.world().get::<Velocity>(player).unwrap();
+    assert!(velocity.linvel.length() < knockback_speed / 2.0);
+
+    game.crash(player, rock, 2.0 / impact_damage);
+    assert!((game.stats().health - (health - 2.0)).abs() < 1e-4);
+    assert!(game.app.world().get::<Invulnerable>(player).is_some());
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use common::TestGame;
+use spacegame::{
+    asteroids::{Asteroid, Blast, Flash},
+    pickups::Pickup,
+};
+
+fn kinds_of_asteroids(game: &mut TestGame) -> Vec<String> {
+    game.asteroids()
+        .into_iter()
+        .map(|(asteroid, _)| asteroid.kind)
+        .collect()
+}
+
+#[test]
+fn metal_takes_several_hits() {
+    let mut game = TestGame::new();
+    let asteroid = game.spawn_asteroid_of("metal", 0.6, Vec2::ZERO);
+
+    // armor halves the damage of every hit
+    for _ in 0..4 {
+        let projectile = game.spawn_projectile();
+        game.collide(projectile, asteroid);
+        assert!(game.exists(asteroid));
+        assert!(!game.exists(projectile));
+    }
+    assert_eq!(game.stats().score, 0);
+
+    let projectile = game.spawn_projectile();
+    game.collide(projectile, asteroid);
+    assert!(!game.exists(asteroid));
+    assert_eq!(game.stats().score, 1);
+}
+
+#[test]
+fn ice_shatters_into_many_pieces() {
+    let mut game = TestGame::new();
+    let asteroid = game.spawn_asteroid_of("ice", 0.6, Vec2::ZERO);
+    let projectile = game.spawn_projectile();
+
+    game.collide(projectile, asteroid);
+
+    let fragments = kinds_of_asteroids(&mut game);
+    assert_eq!(fragments.len(), 6);
+    assert!(fragments.iter().all(|kind| kind == "ice"));
+}
+
+#[test]
+fn explosions_set_off_chain_reactions() {
+    let mut game = TestGame::new();
+    let player = game.spawn_player();
+    game.app
+        .world_mut()
+        .entity_mut(player)
+        .insert(Transform::from_xyz(-30.0, 0.0, 0.0));
+    let first = game.spawn_asteroid_of("explosive", 0.5, Vec2::ZERO);
+    // only in reach of the second explosion
+    let second = game.spawn_asteroid_of("explosive", 0.5, Vec2::new(45.0, 0.0));
+    let rock = game.spawn_asteroid_of("rock", 0.3, Vec2::new(90.0, 0.0));
+    // too big to break from a single blast
+    let big = game.spawn_asteroid_of("rock", 0.6, Vec2::new(45.0, 45.0));
+    let far = game.spawn_asteroid_of("rock", 0.5, Vec2::new(-60.0, 80.0));
+    let projectile = game.spawn_projectile();
+
+    game.collide(projectile, first);
+
+    for asteroid in [first, second, rock] {
+        assert!(!game.exists(asteroid));
+    }
+    assert!(game.exists(far));
+    assert!(game.app.world().entity(big).contains::<Flash>());
+    assert!(!game.app.world().entity(far).contains::<Flash>());
+    assert_eq!(game.stats().score, 3);
+    assert_eq!(game.stats().health, 2.0);
+    assert_eq!(game.count::<Blast>(), 2);
+    // the rock still broke apart, explosives don't
+    assert!(kinds_of_asteroids(&mut game).len() > 1);
+    assert!(
+        kinds_of_asteroids(&mut game)
+            .iter()
+            .all(|kind| kind == "rock")
+    );
+}
+
+#[test]
+fn explosions_reach_across_the_edges() {
+    let mut game = TestGame::new();
+    let player = game.spawn_player();
+    game.app
+        .world_mut()
+        .entity_mut(player)
+        .insert(Transform::from_xyz(-145.0, 20.0, 0.0));
+    let explosive = game.spawn_asteroid_of("explosive", 0.5, Vec2::new(155.0, 0.0));
+    let rock = game.spawn_asteroid_of("rock", 0.3, Vec2::new(-150.0, 0.0));
+    let projectile = game.spawn_projectile();
+
+    game.collide(projectile, explosive);
+
+    assert!(!game.exists(rock));
+    assert_eq!(game.stats().health, 2.0);
+    // pushed on across the edge, away from the blast
+    let velocity = game.app.world().get::<Velocity>(player).unwrap();
+    assert!(velocity.linvel.x > 0.0 && velocity.linvel.y > 0.0);
+}
+
+#[test]
+fn crystals_leave_a_pickup_that_heals() {
+    let mut game = TestGame::new();
+    let player = game.spawn_player();
+    let asteroid = game.spawn_asteroid_of("crystal", 0.3, Vec2::ZERO);
+    let projectile = game.spawn_projectile();
+
+    game.collide(projectile, asteroid);
+    assert_eq!(game.count::<Asteroid>(), 0);
+
+    let pickup = game
+        .app
+        .world_mut()
+        .query_filtered::<Entity, With<Pickup>>()
+        .single(game.app.world())
+        .unwrap();
+    game.stats_mut().health = 1.0;
+    game.collide(player, pickup);
+
+    assert!(!game.exists(pickup));
+    assert_eq!(game.stats().health, 2.0);
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use bevy::{input::keyboard::Key, prelude::*};
+use common::{count, game_state, main_menu, play_for_a_while, step_by_step, tap};
+use spacegame::{
+    FIXED_HZ,
+    asteroids::Asteroid,
+    collisions::Stats,
+    mode::GameMode,
+    player::Player,
+    rng::GameRng,
+    shooting::Projectile,
+    ui::{GameState, HighScores, health_text},
+};
+
+fn run_for_seconds(app: &mut App, seconds: u32) {
+    step_by_step(app);
+    for _ in 0..seconds * FIXED_HZ as u32 {
+        app.update();
+    }
+}
+
+#[test]
+fn asteroids_drift_behind_the_main_menu() {
+    let mut app = main_menu();
+    assert_eq!(game_state(&app), GameState::MainMenu);
+
+    run_for_seconds(&mut app, 10);
+
+    assert_eq!(game_state(&app), GameState::MainMenu);
+    assert_eq!(count::<Player>(&mut app), 0);
+    assert!(count::<Asteroid>(&mut app) > 0);
+}
+
+#[test]
+fn new_game_starts_a_fresh_run_with_the_chosen_mode() {
+    let mut app = main_menu();
+    run_for_seconds(&mut app, 10);
+
+    // the second item cycles the game mode
+    tap(&mut app, KeyCode::ArrowDown, Key::ArrowDown);
+    tap(&mut app, KeyCode::Enter, Key::Enter);
+    assert_eq!(*app.world().resource::<GameMode>(), GameMode::Practice);
+
+    tap(&mut app, KeyCode::ArrowUp, Key::ArrowUp);
+    tap(&mut app, KeyCode::Enter, Key::Enter);
+
+    assert_eq!(game_state(&app), GameState::InGame);
+    assert_eq!(count::<Player>(&mut app), 1);
+    // the attract screen doesn't change the seed of the first run
+    assert_eq!(app.world().resource::<GameRng>().seed(), 0);
+}
+
+#[test]
+fn game_over_returns_to_the_main_menu() {
+    let mut app = main_menu();
+    tap(&mut app, KeyCode::Enter, Key::Enter);
+    play_for_a_while(&mut app);
+    assert!(count::<Projectile>(&mut app) > 0);
+
+    app.world_mut().resource_mut::<Stats>().score = 10;
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::GameOver);
+    app.update();
+    tap(&mut app, KeyCode::Escape, Key::Escape);
+
+    assert_eq!(game_state(&app), GameState::MainMenu);
+    assert_eq!(count::<Player>(&mut app), 0);
+    assert_eq!(count::<Projectile>(&mut app), 0);
+    assert_eq!(
+        app.world().resource::<HighScores>().best(GameMode::Classic),
+        Some(10)
+    );
+
+    tap(&mut app, KeyCode::Enter, Key::Enter);
+    assert_eq!(game_state(&app), GameState::InGame);
+    assert_eq!(app.world().resource::<Stats>().score, 0);
+    assert_ne!(app.world().resource::<GameRng>().seed(), 0);
+}
+
+#[test]
+fn health_is_shown_to_one_decimal() {
+    assert_eq!(health_text(3.0), "3.0");
+    assert_eq!(health_text(2.863), "2.9");
+    // still alive, however little is left
+    assert_eq!(health_text(0.01), "0.1");
+    assert_eq!(health_text(-0.4), "0.0");
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use bevy::{ecs::system::RunSystemOnce, input::keyboard::Key, prelude::*};
+use bevy_rapier2d::prelude::*;
+use common::{count, fixed_step_game, full_game, game_state, play_for_a_while, tap};
+use spacegame::{
+    FIXED_HZ,
+    asteroids::{Asteroid, Blast, Flash, spawn_blast},
+    collisions::Stats,
+    mode::GameMode,
+    player::Player,
+    shooting::{Fizzle, Projectile, ProjectilesData},
+    tuning::WeaponTuning,
+    ui::GameState,
+    waves::WaveDirector,
+};
+
+/// Everything that moves or counts down while playing.
+fn snapshot(app: &mut App) -> (Vec<Vec3>, std::time::Duration, std::time::Duration) {
+    let positions = app
+        .world_mut()
+        .query_filtered::<&Transform, With<RigidBody>>()
+        .iter(app.world())
+        .map(|transform| transform.translation)
+        .collect();
+    let wave_timer = app.world().resource::<WaveDirector>().timer.elapsed();
+    let cooldown = app.world().resource::<ProjectilesData>().cooldown.elapsed();
+    (positions, wave_timer, cooldown)
+}
+
+#[test]
+fn pausing_freezes_the_run() {
+    let mut app = full_game();
+    play_for_a_while(&mut app);
+    assert!(count::<Projectile>(&mut app) > 0);
+
+    tap(&mut app, KeyCode::Escape, Key::Escape);
+    assert_eq!(game_state(&app), GameState::Paused);
+
+    let before = snapshot(&mut app);
+    for _ in 0..60 {
+        app.update();
+    }
+    assert_eq!(snapshot(&mut app), before);
+
+    tap(&mut app, KeyCode::KeyP, Key::Character("p".into()));
+    assert_eq!(game_state(&app), GameState::InGame);
+    app.update();
+    assert_ne!(snapshot(&mut app), before);
+}
+
+#[test]
+fn effects_wait_while_paused() {
+    let mut app = fixed_step_game(GameMode::Classic);
+    let range = WeaponTuning::default().range;
+    app.world_mut().spawn((
+        Projectile {
+            travelled: range,
+            ..Projectile::new(range, 1.0)
+        },
+        Sprite::default(),
+        Transform::default(),
+        Velocity::zero(),
+    ));
+    app.world_mut()
+        .run_system_once(
+            |mut commands: Commands, mut images: ResMut<Assets<Image>>| {
+                spawn_blast(&mut commands, &mut images, Vec3::ZERO, 20.0);
+            },
+        )
+        .unwrap();
+    let asteroid = app
+        .world_mut()
+        .spawn((
+            Asteroid {
+                kind: "rock".into(),
+                score: 1,
+                scale: 0.5,
+            },
+            Flash::default(),
+            Sprite::default(),
+        ))
+        .id();
+
+    tap(&mut app, KeyCode::Escape, Key::Escape);
+    assert_eq!(count::<Fizzle>(&mut app), 1);
+    assert_eq!(count::<Blast>(&mut app), 1);
+    for _ in 0..FIXED_HZ as u32 {
+        app.update();
+    }
+    assert_eq!(count::<Fizzle>(&mut app), 1);
+    assert_eq!(count::<Blast>(&mut app), 1);
+    assert!(app.world().entity(asteroid).contains::<Flash>());
+
+    tap(&mut app, KeyCode::Escape, Key::Escape);
+    for _ in 0..FIXED_HZ as u32 {
+        app.update();
+    }
+    assert_eq!(count::<Fizzle>(&mut app), 0);
+    assert_eq!(count::<Blast>(&mut app), 0);
+    assert!(!app.world().entity(asteroid).contains::<Flash>());
+}
+
+#[test]
+fn restart_from_pause_menu() {
+    let mut app = full_game();
+    play_for_a_while(&mut app);
+    app.world_mut().resource_mut::<Stats>().score = 10;
+
+    tap(&mut app, KeyCode::Escape, Key::Escape);
+    // the second item is Restart
+    tap(&mut app, KeyCode::ArrowDown, Key::ArrowDown);
+    tap(&mut app, KeyCode::Enter, Key::Enter);
+
+    assert_eq!(game_state(&app), GameState::InGame);
+    assert_eq!(app.world().resource::<Stats>().score, 0);
+    assert_eq!(count::<Projectile>(&mut app), 0);
+    assert_eq!(count::<Player>(&mut app), 1);
+    assert!(count::<Asteroid>(&mut app) <= 1);
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use bevy::{input::keyboard::Key, prelude::*, window::WindowFocused};
+use common::{game_state, main_menu_with, step_by_step, tap};
+use spacegame::{
+    asteroids::Asteroid,
+    collisions::Stats,
+    menu::{MenuItem, MenuPointer},
+    player::Player,
+    replay::{RecordPlugin, Replay, ReplayPlugin},
+    ui::GameState,
+};
+
+/// Everything a replay has to reproduce.
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    state: GameState,
+    score: u32,
+    health: f32,
+    player: Vec3,
+    asteroids: Vec<Vec3>,
+}
+
+fn outcome(app: &mut App) -> Outcome {
+    let stats = app.world().resource::<Stats>();
+    let (score, health) = (stats.score, stats.health);
+    let player = app
+        .world_mut()
+        .query_filtered::<&Transform, With<Player>>()
+        .single(app.world())
+        .unwrap()
+        .translation;
+    let asteroids = app
+        .world_mut()
+        .query_filtered::<&Transform, With<Asteroid>>()
+        .iter(app.world())
+        .map(|transform| transform.translation)
+        .collect();
+    Outcome {
+        state: game_state(app),
+        score,
+        health,
+        player,
+        asteroids,
+    }
+}
+
+fn fly(app: &mut App, frames: u32) {
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::ArrowUp);
+    for _ in 0..frames {
+        app.update();
+    }
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .release(KeyCode::ArrowUp);
+}
+
+#[test]
+fn replay_reproduces_a_run_paused_by_focus_loss_and_resumed_by_click() {
+    let path = std::env::temp_dir().join(format!("spacegame-replay-{}.ron", std::process::id()));
+
+    let mut app = main_menu_with(RecordPlugin { path: path.clone() });
+    app.update();
+    step_by_step(&mut app);
+    tap(&mut app, KeyCode::Enter, Key::Enter);
+    assert_eq!(game_state(&app), GameState::InGame);
+    fly(&mut app, 60);
+
+    app.world_mut().send_event(WindowFocused {
+        window: Entity::PLACEHOLDER,
+        focused: false,
+    });
+    app.update();
+    app.update();
+    assert_eq!(game_state(&app), GameState::Paused);
+    fly(&mut app, 30);
+
+    app.world_mut().send_event(MenuPointer {
+        item: MenuItem::Resume,
+        pressed: true,
+    });
+    app.update();
+    app.update();
+    assert_eq!(game_state(&app), GameState::InGame);
+    fly(&mut app, 60);
+
+    let recorded = outcome(&mut app);
+    app.world_mut().send_event(AppExit::Success);
+    app.update();
+
+    let replay = Replay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(replay.frames.iter().any(|frame| frame.focus_lost));
+    assert!(replay.frames.iter().any(|frame| !frame.menu.is_empty()));
+
+    // the last frame is the one the recording exited on
+    let frames = replay.frames.len() - 1;
+    let mut app = main_menu_with(ReplayPlugin { replay });
+    let mut paused = false;
+    for _ in 0..frames {
+        app.update();
+        paused |= game_state(&app) == GameState::Paused;
+        // the window of the replay losing focus doesn't change the outcome
+        app.world_mut().send_event(WindowFocused {
+            window: Entity::PLACEHOLDER,
+            focused: false,
+        });
+    }
+
+    assert!(paused);
+    assert_eq!(outcome(&mut app), recorded);
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use std::time::Duration;
+
+use bevy::{input::keyboard::Key, prelude::*, time::TimeUpdateStrategy};
+use bevy_rapier2d::prelude::*;
+use common::{count, full_game, play_for_a_while, tap};
+use spacegame::{
+    asteroids::Asteroid,
+    collisions::Stats,
+    player::Player,
+    shooting::{Projectile, ProjectilesData},
+    ui::GameState,
+    waves::{WaveDirector, WavePhase},
+};
+
+#[test]
+fn restart_resets_the_whole_world() {
+    let mut app = full_game();
+    play_for_a_while(&mut app);
+    assert!(count::<Asteroid>(&mut app) > 0);
+    assert!(count::<Projectile>(&mut app) > 0);
+
+    // stop the clock so the new run is checked before anything happens in it
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+    app.world_mut().resource_mut::<Stats>().score = 10;
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::GameOver);
+    app.update();
+    tap(&mut app, KeyCode::Space, Key::Space);
+
+    assert_eq!(
+        *app.world().resource::<State<GameState>>().get(),
+        GameState::InGame
+    );
+    assert_eq!(count::<Asteroid>(&mut app), 0);
+    assert_eq!(count::<Projectile>(&mut app), 0);
+    assert_eq!(count::<Player>(&mut app), 1);
+
+    let stats = app.world().resource::<Stats>();
+    assert_eq!(stats.score, 0);
+    assert_eq!(stats.health, 3.0);
+    assert_eq!(
+        app.world()
+            .resource::<ProjectilesData>()
+            .cooldown
+            .duration(),
+        Duration::ZERO
+    );
+
+    let (transform, velocity) = app
+        .world_mut()
+        .query_filtered::<(&Transform, &Velocity), With<Player>>()
+        .single(app.world())
+        .unwrap();
+    assert_eq!(transform.translation, Vec3::ZERO);
+    assert_eq!(transform.rotation, Quat::IDENTITY);
+    assert_eq!(*velocity, Velocity::zero());
+
+    let director = app.world().resource::<WaveDirector>();
+    assert_eq!(director.wave, 1);
+    assert_eq!(director.phase, WavePhase::Announcing);
+    assert_eq!(director.timer.elapsed(), Duration::ZERO);
+}
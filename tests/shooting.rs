@@ -0,0 +1,81 @@
+mod common;
+
+use bevy::{input::keyboard::Key, prelude::*};
+use bevy_rapier2d::prelude::*;
+use common::{count, fixed_step_game, place_player, player, spawn_kind, tap};
+use spacegame::{
+    FIXED_HZ,
+    mode::GameMode,
+    shooting::{Fizzle, Projectile},
+    tuning::WeaponTuning,
+};
+
+fn projectile(app: &mut App) -> Option<(f32, f32)> {
+    app.world_mut()
+        .query::<(&Projectile, &Sprite)>()
+        .single(app.world())
+        .ok()
+        .map(|(projectile, sprite)| (projectile.travelled, sprite.color.alpha()))
+}
+
+#[test]
+fn projectiles_fade_out_at_the_end_of_their_range() {
+    let mut app = fixed_step_game(GameMode::Practice);
+
+    // mostly sideways, which used to make them vanish right away
+    place_player(
+        &mut app,
+        Transform::from_rotation(Quat::from_rotation_z(1.4)),
+    );
+
+    tap(&mut app, KeyCode::Space, Key::Space);
+    let range = WeaponTuning::default().range;
+
+    let mut faded = false;
+    let mut frames = 0;
+    while let Some((travelled, alpha)) = projectile(&mut app) {
+        frames += 1;
+        assert!(
+            frames < 5 * FIXED_HZ as u32,
+            "still flying after {travelled} pixels"
+        );
+        if travelled < range / 2.0 {
+            assert_eq!(alpha, 1.0);
+        } else if travelled > range * 0.9 {
+            faded |= alpha < 0.5;
+        }
+        app.update();
+    }
+    assert!(faded);
+
+    assert_eq!(count::<Fizzle>(&mut app), 1);
+    for _ in 0..FIXED_HZ as u32 {
+        app.update();
+    }
+    assert_eq!(count::<Fizzle>(&mut app), 0);
+}
+
+#[test]
+fn fast_projectiles_hit_the_smallest_fragments() {
+    // a few distances, so that without CCD some shot would jump right over the fragment
+    for distance in [100.0, 102.0, 104.0, 106.0] {
+        let mut app = fixed_step_game(GameMode::Classic);
+
+        // at full speed, the ship's velocity adds to the projectile's
+        let player = player(&mut app);
+        app.world_mut()
+            .entity_mut(player)
+            .insert(Velocity::linear(Vec2::new(0.0, 300.0)));
+        let fragment = spawn_kind(&mut app, "ice", Vec2::new(0.0, distance), Vec2::ZERO, 0.1);
+
+        tap(&mut app, KeyCode::Space, Key::Space);
+        // well before the ship gets there itself
+        for _ in 0..12 {
+            app.update();
+        }
+        assert!(
+            app.world().get_entity(fragment).is_err(),
+            "flew past the fragment {distance} pixels ahead"
+        );
+    }
+}
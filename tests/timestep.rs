@@ -0,0 +1,65 @@
+mod common;
+
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use bevy_rapier2d::prelude::*;
+use common::full_game;
+use spacegame::{FIXED_HZ, player::Player};
+
+/// Flies a turning, accelerating ship for `frames` frames that take `frame_times` in turn,
+/// and returns how fast it ended up.
+fn fly(frame_times: &[Duration], frames: usize) -> Velocity {
+    let mut app = full_game();
+
+    let mut kb_input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+    kb_input.press(KeyCode::ArrowLeft);
+    kb_input.press(KeyCode::ArrowUp);
+
+    for frame_time in frame_times.iter().cycle().take(frames) {
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(*frame_time));
+        app.update();
+    }
+
+    *app.world_mut()
+        .query_filtered::<&Velocity, With<Player>>()
+        .single(app.world())
+        .unwrap()
+}
+
+fn step() -> Duration {
+    Time::<Fixed>::from_hz(FIXED_HZ).timestep()
+}
+
+/// `parts` frame times that add up to exactly one fixed step.
+fn split_step(parts: u32) -> Vec<Duration> {
+    let part = step() / parts;
+    let mut frame_times = vec![part; parts as usize - 1];
+    frame_times.push(step() - part * (parts - 1));
+    frame_times
+}
+
+#[test]
+fn flying_does_not_depend_on_frame_rate() {
+    // 30 and 240 frames per second
+    let slow = fly(&[step() * 2], 30);
+    let fast = fly(&split_step(4), 240);
+
+    assert_ne!(slow.angvel, 0.0);
+    assert_ne!(slow.linvel, Vec2::ZERO);
+    assert_eq!(slow.angvel, fast.angvel);
+    assert_eq!(slow.linvel, fast.linvel);
+}
+
+#[test]
+fn uneven_frame_times_fly_the_same() {
+    let mut frame_times = split_step(3);
+    frame_times.push(step());
+    frame_times.push(step() * 3);
+    // five frames make up five steps
+    let uneven = fly(&frame_times, 60);
+    let even = fly(&[step()], 60);
+
+    assert_eq!(uneven.angvel, even.angvel);
+    assert_eq!(uneven.linvel, even.linvel);
+}
@@ -0,0 +1,52 @@
+use spacegame::tuning::{Tuning, TuningError};
+
+fn invalid_field(tuning: &Tuning) -> Option<&'static str> {
+    match tuning.validate() {
+        Err(TuningError::Invalid { field, .. }) => Some(field),
+        _ => None,
+    }
+}
+
+#[test]
+fn shipped_tuning_is_valid() {
+    Tuning::parse(include_bytes!("../tuning.ron")).unwrap();
+}
+
+#[test]
+fn ship_needs_a_collider_and_no_negative_damping() {
+    let mut tuning = Tuning::default();
+    tuning.player.collider_radius = 0.0;
+    assert_eq!(invalid_field(&tuning), Some("player.collider_radius"));
+
+    let mut tuning = Tuning::default();
+    tuning.player.linear_damping = -1.0;
+    assert_eq!(invalid_field(&tuning), Some("player.linear_damping"));
+
+    let mut tuning = Tuning::default();
+    tuning.player.angular_damping = -1.0;
+    assert_eq!(invalid_field(&tuning), Some("player.angular_damping"));
+}
+
+#[test]
+fn infinite_values_are_rejected() {
+    let tuning = Tuning::parse(b"(player: (invulnerability_s: inf))");
+    assert!(matches!(
+        tuning,
+        Err(TuningError::Invalid {
+            field: "player.invulnerability_s",
+            ..
+        })
+    ));
+
+    let mut tuning = Tuning::default();
+    tuning.waves.spawn_interval_s = f32::INFINITY;
+    assert_eq!(invalid_field(&tuning), Some("waves.spawn_interval_s"));
+
+    let mut tuning = Tuning::default();
+    tuning.asteroids.speed = 0.0..f32::INFINITY;
+    assert_eq!(invalid_field(&tuning), Some("asteroids.speed"));
+
+    let mut tuning = Tuning::default();
+    tuning.weapon.max_shoot_delta_s = f32::NAN;
+    assert_eq!(invalid_field(&tuning), Some("weapon.max_shoot_delta_s"));
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use common::{count, fixed_step_game, place_player};
+use spacegame::{
+    FIXED_HZ, RES_HEIGHT, RES_WIDTH,
+    asteroids::Asteroid,
+    mode::GameMode,
+    tuning::{AsteroidTuning, WaveTuning},
+    waves::{Wave, WaveDirector, WavePhase},
+    wrap::wrapped_distance,
+};
+
+fn director(app: &App) -> &WaveDirector {
+    app.world().resource::<WaveDirector>()
+}
+
+#[test]
+fn waves_get_bigger_and_faster() {
+    let tuning = WaveTuning::default();
+    let first = Wave::nth(&tuning, 1);
+    let fifth = Wave::nth(&tuning, 5);
+    let hundredth = Wave::nth(&tuning, 100);
+
+    assert_eq!(first.small + first.large, tuning.first_wave);
+    assert_eq!(first.speed_multiplier, 1.0);
+    assert!(fifth.small + fifth.large > first.small + first.large);
+    assert!(fifth.large > first.large);
+    assert!(fifth.spawn_interval_s < first.spawn_interval_s);
+    assert!(fifth.speed_multiplier > first.speed_multiplier);
+
+    assert_eq!(hundredth.small, 0);
+    assert_eq!(hundredth.spawn_interval_s, tuning.min_spawn_interval_s);
+    assert_eq!(hundredth.speed_multiplier, tuning.max_speed_multiplier);
+}
+
+#[test]
+fn next_wave_comes_once_the_field_is_clear() {
+    // asteroids flying into the ship shouldn't end the run
+    let mut app = fixed_step_game(GameMode::Practice);
+
+    assert_eq!(director(&app).phase, WavePhase::Announcing);
+
+    for _ in 0..20 * FIXED_HZ as u32 {
+        app.update();
+        if director(&app).phase == WavePhase::Clearing {
+            break;
+        }
+    }
+    assert_eq!(director(&app).phase, WavePhase::Clearing);
+    assert_eq!(
+        count::<Asteroid>(&mut app),
+        WaveTuning::default().first_wave as usize
+    );
+
+    let asteroids: Vec<Entity> = app
+        .world_mut()
+        .query_filtered::<Entity, With<Asteroid>>()
+        .iter(app.world())
+        .collect();
+    for asteroid in asteroids {
+        app.world_mut().despawn(asteroid);
+    }
+    app.update();
+
+    assert_eq!(director(&app).wave, 2);
+    assert_eq!(director(&app).phase, WavePhase::Announcing);
+}
+
+#[test]
+fn asteroids_enter_from_the_edges_away_from_the_ship() {
+    let mut app = fixed_step_game(GameMode::Practice);
+
+    // right next to the edge, so some spawn points on that side
+    // and just across it on the other side are too close
+    let ship = Vec2::new(RES_WIDTH as f32 / 2.0 - 5.0, 0.0);
+    place_player(&mut app, Transform::from_translation(ship.extend(0.0)));
+
+    let safe_radius = AsteroidTuning::default().safe_radius;
+    let mut seen = HashSet::new();
+    for _ in 0..10 * FIXED_HZ as u32 {
+        app.update();
+
+        let spawned: Vec<(Entity, Vec2, Vec2)> = app
+            .world_mut()
+            .query_filtered::<(Entity, &Transform, &Velocity), With<Asteroid>>()
+            .iter(app.world())
+            .filter(|(entity, _, _)| !seen.contains(entity))
+            .map(|(entity, transform, velocity)| {
+                (entity, transform.translation.xy(), velocity.linvel)
+            })
+            .collect();
+        for (entity, position, linvel) in spawned {
+            seen.insert(entity);
+            assert!(
+                position.x.abs() > RES_WIDTH as f32 / 2.0
+                    || position.y.abs() > RES_HEIGHT as f32 / 2.0,
+                "spawned on screen at {position}"
+            );
+            assert!(
+                wrapped_distance(position, ship) >= safe_radius,
+                "spawned at {position}, right across the edge from the ship"
+            );
+            assert!(linvel.dot(-position) > 0.0, "not heading into the field");
+        }
+    }
+    assert_eq!(seen.len(), WaveTuning::default().first_wave as usize);
+}
+
+#[test]
+fn spawning_waits_while_too_many_asteroids_are_around() {
+    let mut app = fixed_step_game(GameMode::Practice);
+    app.insert_resource(AsteroidTuning {
+        max_alive: 2,
+        ..default()
+    });
+
+    for _ in 0..10 * FIXED_HZ as u32 {
+        app.update();
+        assert!(count::<Asteroid>(&mut app) <= 2);
+    }
+    assert_eq!(count::<Asteroid>(&mut app), 2);
+    assert_eq!(director(&app).phase, WavePhase::Spawning);
+}
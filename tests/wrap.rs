@@ -0,0 +1,109 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use common::fixed_step_game;
+use spacegame::{
+    FIXED_HZ, RES_HEIGHT, RES_WIDTH,
+    interpolation::Interpolated,
+    mode::GameMode,
+    wrap::{ScreenWrap, wrapped_distance},
+};
+
+fn spawn_rock(app: &mut App, x: f32, linvel: Vec2, wrap: ScreenWrap) -> Entity {
+    let transform = Transform::from_xyz(x, 0.0, 0.0);
+    app.world_mut()
+        .spawn((
+            Sprite::default(),
+            transform,
+            Interpolated::from(transform),
+            RigidBody::Dynamic,
+            GravityScale(0.0),
+            Velocity::linear(linvel),
+            wrap,
+        ))
+        .id()
+}
+
+fn x(app: &App, entity: Entity) -> f32 {
+    app.world().get::<Transform>(entity).unwrap().translation.x
+}
+
+#[test]
+fn leaving_on_one_side_comes_back_on_the_other() {
+    let mut app = fixed_step_game(GameMode::Practice);
+    let rock = spawn_rock(
+        &mut app,
+        150.0,
+        Vec2::new(120.0, 0.0),
+        ScreenWrap::default(),
+    );
+
+    for _ in 0..FIXED_HZ as u32 / 2 {
+        app.update();
+    }
+
+    // 58 pixels further, through the right edge,
+    // the body only starts moving in the step after the one it was added in
+    assert!((x(&app, rock) - (208.0 - RES_WIDTH as f32)).abs() < 1e-3);
+    let velocity = app.world().get::<Velocity>(rock).unwrap();
+    assert_eq!(velocity.linvel, Vec2::new(120.0, 0.0));
+}
+
+#[test]
+fn entering_from_outside_only_wraps_once_on_the_canvas() {
+    let mut app = fixed_step_game(GameMode::Practice);
+    let rock = spawn_rock(
+        &mut app,
+        200.0,
+        Vec2::new(-120.0, 0.0),
+        ScreenWrap::entering(),
+    );
+
+    app.update();
+    assert!(x(&app, rock) > 190.0);
+
+    for _ in 0..FIXED_HZ as u32 {
+        app.update();
+    }
+    assert!((x(&app, rock) - 80.0).abs() < 1e-3);
+    assert!(app.world().get::<ScreenWrap>(rock).unwrap().on_canvas);
+}
+
+#[test]
+fn ghosts_show_up_on_the_opposite_side() {
+    let mut app = fixed_step_game(GameMode::Practice);
+    let rock = spawn_rock(&mut app, 155.0, Vec2::ZERO, ScreenWrap::default());
+    app.update();
+
+    let children = app.world().get::<Children>(rock).unwrap().to_vec();
+    let mut ghosts: Vec<Vec2> = children
+        .iter()
+        .map(|child| {
+            app.world()
+                .get::<GlobalTransform>(*child)
+                .unwrap()
+                .translation()
+                .xy()
+        })
+        .collect();
+    ghosts.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+
+    let left = 155.0 - RES_WIDTH as f32;
+    assert_eq!(ghosts.len(), 3);
+    assert!(ghosts[0].abs_diff_eq(Vec2::new(left, -180.0), 1e-3));
+    assert!(ghosts[1].abs_diff_eq(Vec2::new(left, 0.0), 1e-3));
+    assert!(ghosts[2].abs_diff_eq(Vec2::new(155.0, -180.0), 1e-3));
+}
+
+#[test]
+fn distances_are_measured_across_the_edges() {
+    let half = Vec2::new(RES_WIDTH as f32, RES_HEIGHT as f32) / 2.0;
+    let near_left = Vec2::new(-half.x + 5.0, 0.0);
+    let past_right = Vec2::new(half.x + 10.0, 3.0);
+    assert!((wrapped_distance(near_left, past_right) - Vec2::new(5.0, 3.0).length()).abs() < 1e-4);
+
+    let corner = half - Vec2::ONE;
+    assert!((wrapped_distance(corner, -corner) - 2.0_f32.sqrt() * 2.0).abs() < 1e-4);
+    assert_eq!(wrapped_distance(Vec2::ZERO, Vec2::new(30.0, 40.0)), 50.0);
+}